@@ -0,0 +1,433 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::components::Component;
+pub use crate::components::ComponentKind;
+use crate::drilled_holes::Hole;
+use crate::flatten::{FlatPanel, flatten_panel};
+use crate::library::Library;
+use crate::validation::{
+    LibraryReferences, PanelReferences, Severity, ValidationConfig, ValidationInput, Validator,
+};
+use crate::{parse_board_file, parse_library_file};
+use std::collections::HashMap;
+
+/// The position of a component within the libraries of an assembly.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct ComponentLink {
+    /// Index of the library in `Assembly::libraries`.
+    pub library: usize,
+    pub kind: ComponentKind,
+    /// Index of the component in the electrical or mechanical components of the library.
+    pub index: usize,
+}
+
+/// A placement together with the board it is on and the library component it refers to.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ResolvedPlacement<'a> {
+    pub board: &'a BoardPanel,
+    pub placement: &'a ComponentPlacement,
+    /// None for placements which do not refer to a library component, i.e. panel `BOARD`
+    /// placements.
+    pub component: Option<Component<'a>>,
+}
+
+/// An assembly of an optional panel, one or more boards and one or more libraries, with all
+/// references between them resolved.
+///
+/// An assembly is either a single board and a library file, or a panel file, 1 or more board files
+/// and a library file. Large designs may split their components across several library files.
+///
+/// # Example
+/// ```
+/// use idf_parser::assembly::Assembly;
+///
+/// let assembly = Assembly::from_files(
+///     Some("src/test_files/panel.emn"),
+///     &["src/test_files/board.emn"],
+///     &["src/test_files/library.emp"],
+/// )
+/// .unwrap();
+///
+/// let c1 = &assembly.placements_by_refdes("C1")[0];
+/// assert_eq!(c1.component.unwrap().part_number(), "pn-cap");
+/// assert_eq!(assembly.panel_boards().len(), 2);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Assembly {
+    panel: Option<BoardPanel>,
+    boards: Vec<BoardPanel>,
+    libraries: Vec<Library>,
+    /// For each board, the library component of each placement.
+    component_links: Vec<Vec<Option<ComponentLink>>>,
+    /// For each panel placement, the index of the board it places.
+    board_links: Vec<Option<usize>>,
+    /// For each board, the index of the placement each hole belongs to.
+    hole_links: Vec<Vec<Option<usize>>>,
+    boards_by_name: HashMap<String, usize>,
+    /// (board index, placement index) of every placement keyed by reference designator.
+    placements_by_refdes: HashMap<String, Vec<(usize, usize)>>,
+    /// (board index, placement index) of every placement keyed by part number.
+    placements_by_part_number: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl Assembly {
+    /// Create an assembly, checking that every placed component is in one of the libraries and
+    /// every board placed on the panel is present.
+    pub fn new(
+        panel: Option<BoardPanel>,
+        boards: Vec<BoardPanel>,
+        libraries: Vec<Library>,
+    ) -> Result<Assembly, String> {
+        if boards.is_empty() {
+            return Err("An assembly requires at least one board.".to_string());
+        }
+        if libraries.is_empty() {
+            return Err("An assembly requires at least one library.".to_string());
+        }
+
+        // Only the reference checks are required for the assembly to be usable.
+        let validator = Validator {
+            rules: vec![Box::new(LibraryReferences), Box::new(PanelReferences)],
+            config: ValidationConfig::default(),
+        };
+        let input = ValidationInput {
+            panel: panel.as_ref(),
+            boards: &boards,
+            libraries: &libraries,
+        };
+        let report = validator.validate(&input);
+        if let Some(error) = report
+            .findings
+            .iter()
+            .find(|f| f.severity == Severity::Error)
+        {
+            return Err(error.message.clone());
+        }
+
+        let mut assembly = Assembly {
+            panel,
+            boards,
+            libraries,
+            component_links: Vec::new(),
+            board_links: Vec::new(),
+            hole_links: Vec::new(),
+            boards_by_name: HashMap::new(),
+            placements_by_refdes: HashMap::new(),
+            placements_by_part_number: HashMap::new(),
+        };
+        assembly.resolve();
+        Ok(assembly)
+    }
+
+    /// Parse an optional panel file, one or more board files and one or more library files into
+    /// an assembly.
+    pub fn from_files(
+        panel_file: Option<&str>,
+        board_files: &[&str],
+        library_files: &[&str],
+    ) -> Result<Assembly, String> {
+        let panel = match panel_file {
+            Some(file) => Some(parse_board_file(file)?),
+            None => None,
+        };
+        let boards = board_files
+            .iter()
+            .map(|file| parse_board_file(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        let libraries = library_files
+            .iter()
+            .map(|file| parse_library_file(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        Assembly::new(panel, boards, libraries)
+    }
+
+    /// Build the links between placements, components, boards and holes.
+    fn resolve(&mut self) {
+        // Components keyed by geometry name and by geometry name and part number. The first
+        // definition wins where a component is defined in more than one library.
+        let mut by_geometry: HashMap<&str, ComponentLink> = HashMap::new();
+        let mut by_geometry_part: HashMap<(&str, &str), ComponentLink> = HashMap::new();
+        for (library_index, library) in self.libraries.iter().enumerate() {
+            let mut counts = HashMap::new();
+            for component in library.components() {
+                let kind = component.kind();
+                let (geometry_name, part_number) =
+                    (component.geometry_name(), component.part_number());
+                let index = counts.entry(kind).or_insert(0);
+                let link = ComponentLink {
+                    library: library_index,
+                    kind,
+                    index: *index,
+                };
+                *index += 1;
+                by_geometry.entry(geometry_name).or_insert(link);
+                by_geometry_part
+                    .entry((geometry_name, part_number))
+                    .or_insert(link);
+            }
+        }
+
+        for (board_index, board) in self.boards.iter().enumerate() {
+            self.boards_by_name
+                .entry(board.header.board_name.clone())
+                .or_insert(board_index);
+
+            let mut links = Vec::new();
+            let mut placements_by_refdes: HashMap<&str, usize> = HashMap::new();
+            for (placement_index, placement) in board.component_placements.iter().enumerate() {
+                let key = (
+                    placement.package_name.as_str(),
+                    placement.part_number.as_str(),
+                );
+                links.push(
+                    by_geometry_part
+                        .get(&key)
+                        .or_else(|| by_geometry.get(key.0))
+                        .copied(),
+                );
+                self.placements_by_refdes
+                    .entry(placement.reference_designator.clone())
+                    .or_default()
+                    .push((board_index, placement_index));
+                self.placements_by_part_number
+                    .entry(placement.part_number.clone())
+                    .or_default()
+                    .push((board_index, placement_index));
+                placements_by_refdes
+                    .entry(&placement.reference_designator)
+                    .or_insert(placement_index);
+            }
+            self.component_links.push(links);
+
+            self.hole_links.push(
+                board
+                    .drilled_holes
+                    .iter()
+                    .map(|hole| match hole.hole_type.as_str() {
+                        "PIN" => placements_by_refdes
+                            .get(hole.associated_part.as_str())
+                            .copied(),
+                        _ => None,
+                    })
+                    .collect(),
+            );
+        }
+
+        if let Some(panel) = &self.panel {
+            self.board_links = panel
+                .component_placements
+                .iter()
+                .map(|placement| match placement.reference_designator.as_str() {
+                    "BOARD" => self.boards_by_name.get(&placement.package_name).copied(),
+                    _ => None,
+                })
+                .collect();
+        }
+    }
+
+    pub fn panel(&self) -> Option<&BoardPanel> {
+        self.panel.as_ref()
+    }
+
+    pub fn boards(&self) -> &[BoardPanel] {
+        &self.boards
+    }
+
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+
+    /// Take the panel, boards and libraries back out of the assembly.
+    pub fn into_parts(self) -> (Option<BoardPanel>, Vec<BoardPanel>, Vec<Library>) {
+        (self.panel, self.boards, self.libraries)
+    }
+
+    /// The library component a link refers to.
+    pub fn component(&self, link: ComponentLink) -> Component<'_> {
+        let library = &self.libraries[link.library];
+        match link.kind {
+            ComponentKind::Electrical => {
+                Component::Electrical(&library.electrical_components[link.index])
+            }
+            ComponentKind::Mechanical => {
+                Component::Mechanical(&library.mechanical_components[link.index])
+            }
+        }
+    }
+
+    /// The link to the library component of a placement, given the index of the board and the
+    /// index of the placement on that board.
+    pub fn component_link(&self, board: usize, placement: usize) -> Option<ComponentLink> {
+        self.component_links
+            .get(board)
+            .and_then(|links| links.get(placement))
+            .copied()
+            .flatten()
+    }
+
+    fn resolved(&self, board: usize, placement: usize) -> ResolvedPlacement<'_> {
+        ResolvedPlacement {
+            board: &self.boards[board],
+            placement: &self.boards[board].component_placements[placement],
+            component: self
+                .component_link(board, placement)
+                .map(|link| self.component(link)),
+        }
+    }
+
+    /// Every placement on every board, resolved.
+    pub fn placements(&self) -> Vec<ResolvedPlacement<'_>> {
+        (0..self.boards.len())
+            .flat_map(|b| (0..self.boards[b].component_placements.len()).map(move |p| (b, p)))
+            .map(|(b, p)| self.resolved(b, p))
+            .collect()
+    }
+
+    /// Look up a board by the name in its header.
+    pub fn board_by_name(&self, name: &str) -> Option<&BoardPanel> {
+        self.boards_by_name.get(name).map(|&i| &self.boards[i])
+    }
+
+    /// All placements with the given reference designator, across every board.
+    pub fn placements_by_refdes(&self, refdes: &str) -> Vec<ResolvedPlacement<'_>> {
+        self.placements_by_refdes
+            .get(refdes)
+            .map(|found| found.iter().map(|&(b, p)| self.resolved(b, p)).collect())
+            .unwrap_or_default()
+    }
+
+    /// The placement with the given reference designator on the named board.
+    pub fn placement(&self, board_name: &str, refdes: &str) -> Option<ResolvedPlacement<'_>> {
+        let board = *self.boards_by_name.get(board_name)?;
+        self.placements_by_refdes
+            .get(refdes)?
+            .iter()
+            .find(|&&(b, _)| b == board)
+            .map(|&(b, p)| self.resolved(b, p))
+    }
+
+    /// All placements with the given part number, across every board.
+    pub fn placements_by_part_number(&self, part_number: &str) -> Vec<ResolvedPlacement<'_>> {
+        self.placements_by_part_number
+            .get(part_number)
+            .map(|found| found.iter().map(|&(b, p)| self.resolved(b, p)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Each `BOARD` placement on the panel together with the board it places.
+    pub fn panel_boards(&self) -> Vec<(&ComponentPlacement, &BoardPanel)> {
+        let Some(panel) = &self.panel else {
+            return Vec::new();
+        };
+        panel
+            .component_placements
+            .iter()
+            .zip(self.board_links.iter())
+            .filter_map(|(placement, link)| link.map(|b| (placement, &self.boards[b])))
+            .collect()
+    }
+
+    /// Expand every board placed on the panel into absolute panel coordinates, returning None
+    /// if the assembly has no panel.
+    pub fn flatten(&self) -> Option<FlatPanel> {
+        let panel = self.panel.as_ref()?;
+        // Every board placed on the panel was resolved when the assembly was created.
+        flatten_panel(panel, &self.boards).ok()
+    }
+
+    /// The placement a PIN hole belongs to, given the index of the board and the index of the
+    /// hole on that board.
+    pub fn hole_placement(&self, board: usize, hole: usize) -> Option<&ComponentPlacement> {
+        let placement = (*self.hole_links.get(board)?.get(hole)?)?;
+        Some(&self.boards[board].component_placements[placement])
+    }
+
+    /// The PIN holes of the placement with the given reference designator on the named board.
+    pub fn pin_holes(&self, board_name: &str, refdes: &str) -> Vec<&Hole> {
+        let Some(&board) = self.boards_by_name.get(board_name) else {
+            return Vec::new();
+        };
+        self.boards[board]
+            .drilled_holes
+            .iter()
+            .zip(self.hole_links[board].iter())
+            .filter(|(_, link)| {
+                link.is_some_and(|p| {
+                    self.boards[board].component_placements[p].reference_designator == refdes
+                })
+            })
+            .map(|(hole, _)| hole)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_assembly() -> Assembly {
+        Assembly::from_files(
+            Some("src/test_files/panel.emn"),
+            &["src/test_files/board.emn"],
+            &["src/test_files/library.emp"],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_assembly_from_files() {
+        let assembly = sample_assembly();
+        assert!(assembly.panel().is_some());
+        assert_eq!(assembly.boards().len(), 1);
+
+        // This panel file references a board that doesn't exist
+        let result = Assembly::from_files(
+            Some("src/test_files/invalid_panel.emn"),
+            &["src/test_files/board.emn"],
+            &["src/test_files/library.emp"],
+        );
+        assert!(result.is_err());
+
+        // A board on its own still needs a library
+        let result = Assembly::from_files(None, &["src/test_files/board.emn"], &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_placement_lookups() {
+        let assembly = sample_assembly();
+
+        let u4 = assembly.placement("sample_board", "U4").unwrap();
+        assert_eq!(u4.placement.rotation_angle, 270.0);
+        assert_eq!(u4.component.unwrap().geometry_name(), "dip_14w");
+        assert!(assembly.placement("other_board", "U4").is_none());
+
+        let capacitors = assembly.placements_by_part_number("pn-cc1210");
+        assert_eq!(capacitors.len(), 4);
+        assert!(
+            capacitors
+                .iter()
+                .all(|c| matches!(c.component, Some(Component::Electrical(_))))
+        );
+        assert_eq!(assembly.placements().len(), 11);
+        assert!(assembly.board_by_name("sample_board").is_some());
+    }
+
+    #[test]
+    fn test_panel_and_hole_links() {
+        let assembly = sample_assembly();
+
+        let panel_boards = assembly.panel_boards();
+        assert_eq!(panel_boards.len(), 2);
+        assert_eq!(panel_boards[1].0.board_side, "BOTTOM");
+        assert_eq!(panel_boards[1].1.header.board_name, "sample_board");
+        assert_eq!(assembly.flatten().unwrap().boards.len(), 2);
+
+        assert_eq!(
+            assembly.hole_placement(0, 0).unwrap().reference_designator,
+            "J1"
+        );
+        assert_eq!(assembly.pin_holes("sample_board", "U3").len(), 14);
+        assert_eq!(assembly.pin_holes("sample_board", "C1").len(), 0);
+    }
+}
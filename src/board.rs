@@ -13,6 +13,8 @@ use nom::multi::{many_m_n, many0};
 
 /// Represents a board or panel file in the IDF format.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BoardPanel {
     pub header: BoardPanelHeader,
     pub outline: BoardPanelOutline,
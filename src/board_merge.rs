@@ -0,0 +1,554 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::diff::pair;
+use crate::drilled_holes::Hole;
+use crate::editing::Domain;
+use crate::flatten::transform_board;
+use crate::geometry::Transform;
+use crate::notes::Note;
+use crate::outlines::{
+    BoardPanelOutline, OtherOutline, PlacementGroupArea, PlacementKeepout, PlacementOutline,
+    RoutingKeepout, RoutingOutline, ViaKeepout,
+};
+use crate::point::Point;
+
+/// How an entity differs between the board that was sent and the board that was returned.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A change made by the returning domain.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct EntityChange {
+    /// The section the entity belongs to, e.g. "placement" or "drilled_hole".
+    pub entity: &'static str,
+    /// Identifies the entity, e.g. a reference designator or hole position.
+    pub description: String,
+    pub kind: ChangeKind,
+    /// Owner, or placement status, of the entity before the change.
+    pub old_owner: Option<String>,
+    /// Owner, or placement status, of the entity after the change.
+    pub new_owner: Option<String>,
+}
+
+/// The changes accepted into and rejected from a merged board.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct BoardMergeReport {
+    pub accepted: Vec<EntityChange>,
+    pub rejected: Vec<EntityChange>,
+}
+
+impl BoardMergeReport {
+    /// Human readable summary of the rejected changes, one per line.
+    pub fn rejected_text(&self, domain: Domain) -> String {
+        self.rejected
+            .iter()
+            .map(|c| {
+                let verb = match c.kind {
+                    ChangeKind::Added => "add",
+                    ChangeKind::Removed => "remove",
+                    ChangeKind::Modified => "modify",
+                };
+                let owner = c.old_owner.as_ref().or(c.new_owner.as_ref());
+                format!(
+                    "{} may not {} {} {} owned by {}\n",
+                    domain.as_str(),
+                    verb,
+                    c.entity,
+                    c.description,
+                    owner.map_or("", |o| o.as_str())
+                )
+            })
+            .collect()
+    }
+}
+
+/// How to identify and check the ownership of one kind of entity.
+struct EntityKind<T> {
+    name: &'static str,
+    owner: fn(&T) -> &str,
+    /// A key shared by a sent and a returned entity that are likely the same entity, to find
+    /// their pair without comparing every entity with every other.
+    key: fn(&T) -> String,
+    /// Tests of whether a sent and a returned entity are the same entity, tried in turn to pair
+    /// modified entities, e.g. the same reference designator or the same outline.
+    matchers: Vec<fn(&T, &T) -> bool>,
+    describe: fn(&T) -> String,
+}
+
+/// Merge one list of entities, keeping the sent entities the domain is not allowed to change.
+///
+/// Entities that are equal in both lists are unchanged. The remaining sent and returned entities
+/// are paired as modifications when one of the kind's matchers says they are the same entity,
+/// and any left over are removals or additions judged on their own owner. A modification is only
+/// accepted if the domain may modify both the old and the new owner, so an entity cannot be
+/// taken from the other domain.
+fn merge_entities<T: PartialEq + Clone>(
+    kind: &EntityKind<T>,
+    sent: &[T],
+    returned: &[T],
+    domain: Domain,
+    report: &mut BoardMergeReport,
+) -> Vec<T> {
+    let mut merged: Vec<Option<T>> = sent.iter().cloned().map(Some).collect();
+    let matchers: Vec<_> = kind
+        .matchers
+        .iter()
+        .map(|m| m as &dyn Fn(&T, &T) -> bool)
+        .collect();
+    let (pairs, removed, added) = pair(sent, returned, kind.key, |a, b| a == b, &matchers);
+
+    let change = |change_kind: ChangeKind, old: Option<&T>, new: Option<&T>| EntityChange {
+        entity: kind.name,
+        description: new.or(old).map(kind.describe).unwrap_or_default(),
+        kind: change_kind,
+        old_owner: old.map(|o| (kind.owner)(o).to_string()),
+        new_owner: new.map(|n| (kind.owner)(n).to_string()),
+    };
+
+    for &(i, j) in pairs.iter() {
+        let (old, new) = (&sent[i], &returned[j]);
+        let c = change(ChangeKind::Modified, Some(old), Some(new));
+        if domain.can_modify((kind.owner)(old)) && domain.can_modify((kind.owner)(new)) {
+            merged[i] = Some(new.clone());
+            report.accepted.push(c);
+        } else {
+            report.rejected.push(c);
+        }
+    }
+
+    for &i in &removed {
+        let c = change(ChangeKind::Removed, Some(&sent[i]), None);
+        if domain.can_modify((kind.owner)(&sent[i])) {
+            merged[i] = None;
+            report.accepted.push(c);
+        } else {
+            report.rejected.push(c);
+        }
+    }
+
+    let mut result: Vec<T> = merged.into_iter().flatten().collect();
+    for &j in &added {
+        let c = change(ChangeKind::Added, None, Some(&returned[j]));
+        if domain.can_modify((kind.owner)(&returned[j])) {
+            result.push(returned[j].clone());
+            report.accepted.push(c);
+        } else {
+            report.rejected.push(c);
+        }
+    }
+    result
+}
+
+fn describe_outline(outline: &[Point]) -> String {
+    match outline.first() {
+        Some(p) => format!("starting at ({}, {})", p.x, p.y),
+        None => "with no points".to_string(),
+    }
+}
+
+fn same_hole_position(a: &Hole, b: &Hole) -> bool {
+    (a.x, a.y) == (b.x, b.y)
+}
+
+/// The same size and kind of hole for the same part, which may have moved.
+fn same_hole(a: &Hole, b: &Hole) -> bool {
+    a.diameter == b.diameter
+        && a.plating_style == b.plating_style
+        && a.associated_part == b.associated_part
+        && a.hole_type == b.hole_type
+}
+
+fn describe_hole(hole: &Hole) -> String {
+    format!(
+        "{} {} at ({}, {}) for {}",
+        hole.diameter, hole.hole_type, hole.x, hole.y, hole.associated_part
+    )
+}
+
+/// Round a length converted between units to 6 significant digits, so that lengths which were
+/// equal before a round trip through other units are equal again.
+fn round_converted(value: f32) -> f32 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let scale = 10f64.powi(5 - (value.abs() as f64).log10().floor() as i32);
+    ((value as f64 * scale).round() / scale) as f32
+}
+
+/// A board converted to other units, with every length rounded by [round_converted].
+fn converted_lengths(board: &BoardPanel, units: &str) -> BoardPanel {
+    let mut board = transform_board(board, &Transform::default(), units);
+    let round = |points: &mut Vec<Point>| {
+        for p in points.iter_mut() {
+            (p.x, p.y) = (round_converted(p.x), round_converted(p.y));
+        }
+    };
+    board.outline.thickness = round_converted(board.outline.thickness);
+    round(&mut board.outline.outline);
+    for o in board.other_outlines.iter_mut() {
+        o.extrude_thickness = round_converted(o.extrude_thickness);
+        round(&mut o.outline);
+    }
+    for o in board.routing_outlines.iter_mut() {
+        round(&mut o.outline);
+    }
+    for o in board.placement_outlines.iter_mut() {
+        o.outline_height = round_converted(o.outline_height);
+        round(&mut o.outline);
+    }
+    for k in board.routing_keepouts.iter_mut() {
+        round(&mut k.outline);
+    }
+    for k in board.via_keepouts.iter_mut() {
+        round(&mut k.outline);
+    }
+    for k in board.placement_keepouts.iter_mut() {
+        k.keepout_height = round_converted(k.keepout_height);
+        round(&mut k.outline);
+    }
+    for a in board.placement_group_areas.iter_mut() {
+        round(&mut a.outline);
+    }
+    for h in board.drilled_holes.iter_mut() {
+        (h.x, h.y) = (round_converted(h.x), round_converted(h.y));
+        h.diameter = round_converted(h.diameter);
+    }
+    for n in board.notes.iter_mut() {
+        (n.x, n.y) = (round_converted(n.x), round_converted(n.y));
+        n.text_height = round_converted(n.text_height);
+        n.test_string_physical_length = round_converted(n.test_string_physical_length);
+    }
+    for p in board.component_placements.iter_mut() {
+        (p.x, p.y) = (round_converted(p.x), round_converted(p.y));
+        p.mounting_offset = round_converted(p.mounting_offset);
+    }
+    board
+}
+
+/// Merge a board returned by one domain into the board last sent to it.
+///
+/// IDF 3.0 gives outlines, keepouts and holes an owner (`ECAD`, `MCAD` or `UNOWNED`), and
+/// placements a status where `ECAD` and `MCAD` restrict changes to that domain. Changes made by
+/// `domain` are accepted only for entities it is allowed to modify, all other changes are
+/// rejected and the sent entity is kept. Notes are not owned and are always taken from the
+/// returned board, as is the header apart from its units.
+///
+/// A board returned in other units is converted to the units of the sent board before the two
+/// are compared, with lengths rounded to 6 significant digits to remove the error of the
+/// conversion, so the merged board is always in the sent board's units.
+///
+/// # Example
+/// ```
+/// use idf_parser::board_merge::merge_returned_board;
+/// use idf_parser::editing::Domain;
+/// use idf_parser::parse_board_file;
+///
+/// let sent = parse_board_file("src/test_files/board.emn").unwrap();
+/// let mut returned = sent.clone();
+/// // The holes of the sample board are owned by ECAD, so MCAD may not move them.
+/// returned.drilled_holes[0].x += 10.0;
+///
+/// let (merged, report) = merge_returned_board(&sent, &returned, Domain::Mcad);
+/// assert_eq!(merged.drilled_holes, sent.drilled_holes);
+/// assert_eq!(report.rejected.len(), 1);
+/// ```
+pub fn merge_returned_board(
+    sent: &BoardPanel,
+    returned: &BoardPanel,
+    domain: Domain,
+) -> (BoardPanel, BoardMergeReport) {
+    let converted;
+    let returned = if returned.header.units == sent.header.units {
+        returned
+    } else {
+        converted = converted_lengths(returned, &sent.header.units);
+        &converted
+    };
+    let mut report = BoardMergeReport::default();
+    let r = &mut report;
+
+    let outline = merge_entities(
+        &EntityKind::<BoardPanelOutline> {
+            name: "outline",
+            owner: |o| &o.owner,
+            key: |_| String::new(),
+            matchers: vec![|_, _| true],
+            describe: |o| describe_outline(&o.outline),
+        },
+        std::slice::from_ref(&sent.outline),
+        std::slice::from_ref(&returned.outline),
+        domain,
+        r,
+    );
+
+    let merged = BoardPanel {
+        header: returned.header.clone(),
+        // The outline is always paired with itself, so exactly one remains.
+        outline: outline.into_iter().next().unwrap_or_default(),
+        other_outlines: merge_entities(
+            &EntityKind::<OtherOutline> {
+                name: "other_outline",
+                owner: |o| &o.owner,
+                key: |o| o.id.clone(),
+                matchers: vec![|a, b| a.id == b.id],
+                describe: |o| o.id.clone(),
+            },
+            &sent.other_outlines,
+            &returned.other_outlines,
+            domain,
+            r,
+        ),
+        routing_outlines: merge_entities(
+            &EntityKind::<RoutingOutline> {
+                name: "routing_outline",
+                owner: |o| &o.owner,
+                key: |o| describe_outline(&o.outline),
+                matchers: vec![|a, b| a.outline == b.outline],
+                describe: |o| describe_outline(&o.outline),
+            },
+            &sent.routing_outlines,
+            &returned.routing_outlines,
+            domain,
+            r,
+        ),
+        placement_outlines: merge_entities(
+            &EntityKind::<PlacementOutline> {
+                name: "placement_outline",
+                owner: |o| &o.owner,
+                key: |o| describe_outline(&o.outline),
+                matchers: vec![|a, b| a.outline == b.outline],
+                describe: |o| describe_outline(&o.outline),
+            },
+            &sent.placement_outlines,
+            &returned.placement_outlines,
+            domain,
+            r,
+        ),
+        routing_keepouts: merge_entities(
+            &EntityKind::<RoutingKeepout> {
+                name: "routing_keepout",
+                owner: |k| &k.owner,
+                key: |k| describe_outline(&k.outline),
+                matchers: vec![|a, b| a.outline == b.outline],
+                describe: |k| describe_outline(&k.outline),
+            },
+            &sent.routing_keepouts,
+            &returned.routing_keepouts,
+            domain,
+            r,
+        ),
+        via_keepouts: merge_entities(
+            &EntityKind::<ViaKeepout> {
+                name: "via_keepout",
+                owner: |k| &k.owner,
+                key: |k| describe_outline(&k.outline),
+                matchers: vec![|a, b| a.outline == b.outline],
+                describe: |k| describe_outline(&k.outline),
+            },
+            &sent.via_keepouts,
+            &returned.via_keepouts,
+            domain,
+            r,
+        ),
+        placement_keepouts: merge_entities(
+            &EntityKind::<PlacementKeepout> {
+                name: "placement_keepout",
+                owner: |k| &k.owner,
+                key: |k| describe_outline(&k.outline),
+                matchers: vec![|a, b| a.outline == b.outline],
+                describe: |k| describe_outline(&k.outline),
+            },
+            &sent.placement_keepouts,
+            &returned.placement_keepouts,
+            domain,
+            r,
+        ),
+        placement_group_areas: merge_entities(
+            &EntityKind::<PlacementGroupArea> {
+                name: "placement_group_area",
+                owner: |a| &a.owner,
+                key: |a| a.group_name.clone(),
+                matchers: vec![|a, b| a.group_name == b.group_name],
+                describe: |a| a.group_name.clone(),
+            },
+            &sent.placement_group_areas,
+            &returned.placement_group_areas,
+            domain,
+            r,
+        ),
+        drilled_holes: merge_entities(
+            &EntityKind::<Hole> {
+                name: "drilled_hole",
+                owner: |h| &h.owner,
+                key: |h| format!("{} {}", h.x, h.y),
+                matchers: vec![same_hole_position, same_hole],
+                describe: describe_hole,
+            },
+            &sent.drilled_holes,
+            &returned.drilled_holes,
+            domain,
+            r,
+        ),
+        notes: merge_entities(
+            &EntityKind::<Note> {
+                name: "note",
+                owner: |_| "UNOWNED",
+                key: |n| n.text.clone(),
+                matchers: vec![|a, b| a.text == b.text, |a, b| (a.x, a.y) == (b.x, b.y)],
+                describe: |n| n.text.clone(),
+            },
+            &sent.notes,
+            &returned.notes,
+            domain,
+            r,
+        ),
+        component_placements: merge_entities(
+            &EntityKind::<ComponentPlacement> {
+                name: "placement",
+                owner: |p| &p.placement_status,
+                key: |p| p.reference_designator.clone(),
+                matchers: vec![|a, b| {
+                    a.reference_designator == b.reference_designator
+                        && a.reference_designator != "NOREFDES"
+                        && a.reference_designator != "BOARD"
+                }],
+                describe: |p| p.reference_designator.clone(),
+            },
+            &sent.component_placements,
+            &returned.component_placements,
+            domain,
+            r,
+        ),
+    };
+    (merged, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_board_file;
+
+    fn sample() -> BoardPanel {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.component_placements[0].placement_status = "ECAD".to_string();
+        board.component_placements[1].placement_status = "MCAD".to_string();
+        board
+    }
+
+    #[test]
+    fn test_unchanged_board() {
+        let sent = sample();
+        let (merged, report) = merge_returned_board(&sent, &sent, Domain::Mcad);
+        assert_eq!(merged, sent);
+        assert_eq!(report, BoardMergeReport::default());
+    }
+
+    #[test]
+    fn test_mcad_changes() {
+        let sent = sample();
+        let mut returned = sent.clone();
+        // Allowed: an MCAD placement, a PLACED placement and an MCAD owned keepout.
+        returned.component_placements[1].x += 100.0;
+        returned.component_placements[2].rotation_angle = 90.0;
+        returned.placement_keepouts[0].keepout_height = 50.0;
+        // Rejected: an ECAD placement, an ECAD hole, and taking ownership of an ECAD hole.
+        returned.component_placements[0].x += 100.0;
+        returned.drilled_holes.remove(0);
+        returned.drilled_holes[0].owner = "MCAD".to_string();
+        // Rejected: adding an ECAD owned hole.
+        let mut new_hole = sent.drilled_holes[0].clone();
+        new_hole.x = -1000.0;
+        returned.drilled_holes.push(new_hole);
+
+        let (merged, report) = merge_returned_board(&sent, &returned, Domain::Mcad);
+        assert_eq!(merged.component_placements[0], sent.component_placements[0]);
+        assert_eq!(
+            merged.component_placements[1],
+            returned.component_placements[1]
+        );
+        assert_eq!(merged.component_placements[2].rotation_angle, 90.0);
+        assert_eq!(merged.placement_keepouts[0].keepout_height, 50.0);
+        assert_eq!(merged.drilled_holes, sent.drilled_holes);
+
+        assert_eq!(report.accepted.len(), 3);
+        let rejected: Vec<(&str, ChangeKind)> =
+            report.rejected.iter().map(|c| (c.entity, c.kind)).collect();
+        assert!(rejected.contains(&("placement", ChangeKind::Modified)));
+        assert!(rejected.iter().any(|(entity, _)| *entity == "drilled_hole"));
+        assert!(
+            report
+                .rejected_text(Domain::Mcad)
+                .contains("MCAD may not modify placement C1 owned by ECAD")
+        );
+    }
+
+    #[test]
+    fn test_ecad_changes() {
+        let sent = sample();
+        let mut returned = sent.clone();
+        returned.component_placements.remove(1);
+        returned.drilled_holes[0].x += 5.0;
+        returned.outline.thickness = 80.0;
+
+        let (merged, report) = merge_returned_board(&sent, &returned, Domain::Ecad);
+        // The MCAD placement may not be removed and the outline is MCAD owned.
+        assert_eq!(merged.component_placements, sent.component_placements);
+        assert_eq!(merged.outline, sent.outline);
+        assert_eq!(merged.drilled_holes[0].x, sent.drilled_holes[0].x + 5.0);
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.rejected.len(), 2);
+    }
+
+    #[test]
+    fn test_unrelated_removal_and_addition_are_judged_separately() {
+        let sent = sample();
+        let mut returned = sent.clone();
+        // MCAD deletes an ECAD pin hole and adds its own mounting hole elsewhere.
+        let removed = returned.drilled_holes.remove(0);
+        let mounting_hole = Hole {
+            diameter: 120.0,
+            x: -500.0,
+            y: -500.0,
+            plating_style: "NPTH".to_string(),
+            associated_part: "BOARD".to_string(),
+            hole_type: "MTG".to_string(),
+            owner: "MCAD".to_string(),
+        };
+        returned.drilled_holes.push(mounting_hole.clone());
+
+        let (merged, report) = merge_returned_board(&sent, &returned, Domain::Mcad);
+        assert!(merged.drilled_holes.contains(&removed));
+        assert!(merged.drilled_holes.contains(&mounting_hole));
+        assert_eq!(merged.drilled_holes.len(), sent.drilled_holes.len() + 1);
+
+        let kinds = |changes: &[EntityChange]| -> Vec<ChangeKind> {
+            changes.iter().map(|c| c.kind).collect()
+        };
+        assert_eq!(kinds(&report.accepted), vec![ChangeKind::Added]);
+        assert_eq!(kinds(&report.rejected), vec![ChangeKind::Removed]);
+    }
+
+    #[test]
+    fn test_returned_in_other_units() {
+        let sent = sample();
+        let mut returned = transform_board(&sent, &Transform::default(), "MM");
+        // MCAD moves its placement by 2.54 mm, and may not move an ECAD hole.
+        returned.component_placements[1].x += 2.54;
+        returned.drilled_holes[0].x += 2.54;
+
+        let (merged, report) = merge_returned_board(&sent, &returned, Domain::Mcad);
+        assert_eq!(merged.header.units, "THOU");
+        assert_eq!(merged.drilled_holes, sent.drilled_holes);
+        // Untouched entities are unchanged by the round trip through millimetres.
+        assert_eq!(merged.outline, sent.outline);
+        assert_eq!(merged.notes, sent.notes);
+        let moved = &merged.component_placements[1];
+        assert!((moved.x - (sent.component_placements[1].x + 100.0)).abs() < 1e-2);
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.rejected.len(), 1);
+    }
+}
@@ -0,0 +1,601 @@
+use crate::board::BoardPanel;
+use crate::component_placement::{ComponentPlacement, component_placement};
+use crate::diff::{BoardDiff, EntityType};
+use crate::drilled_holes::{Hole, drilled_hole};
+use crate::flatten::transform_board;
+use crate::geometry::Transform;
+use crate::notes::{Note, parse_notes_section};
+use crate::outlines::{
+    BoardPanelOutline, OtherOutline, PlacementGroupArea, PlacementKeepout, PlacementOutline,
+    RoutingKeepout, RoutingOutline, ViaKeepout, parse_board_panel_outline, parse_other_outline,
+    parse_placement_group_area, parse_placement_keepout, parse_placement_outline,
+    parse_routing_keepout, parse_routing_outline, parse_via_keepout,
+};
+use crate::primitives::ws;
+use crate::writer;
+use crate::{parse_section, ws_separated};
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag};
+use nom::combinator::map_opt;
+use nom::multi::many0;
+use nom::sequence::delimited;
+use nom::{IResult, Parser};
+
+/// A single entity of a board, as it appears in a changeset.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub enum Entity {
+    Outline(BoardPanelOutline),
+    OtherOutline(OtherOutline),
+    RoutingOutline(RoutingOutline),
+    PlacementOutline(PlacementOutline),
+    RoutingKeepout(RoutingKeepout),
+    ViaKeepout(ViaKeepout),
+    PlacementKeepout(PlacementKeepout),
+    PlacementGroupArea(PlacementGroupArea),
+    DrilledHole(Hole),
+    Note(Note),
+    Placement(ComponentPlacement),
+}
+
+impl Entity {
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            Entity::Outline(_) => EntityType::Outline,
+            Entity::OtherOutline(_) => EntityType::OtherOutline,
+            Entity::RoutingOutline(_) => EntityType::RoutingOutline,
+            Entity::PlacementOutline(_) => EntityType::PlacementOutline,
+            Entity::RoutingKeepout(_) => EntityType::RoutingKeepout,
+            Entity::ViaKeepout(_) => EntityType::ViaKeepout,
+            Entity::PlacementKeepout(_) => EntityType::PlacementKeepout,
+            Entity::PlacementGroupArea(_) => EntityType::PlacementGroupArea,
+            Entity::DrilledHole(_) => EntityType::DrilledHole,
+            Entity::Note(_) => EntityType::Note,
+            Entity::Placement(_) => EntityType::Placement,
+        }
+    }
+
+    /// The entity at `index` in the list of entities of the given type, the outline having
+    /// index 0.
+    fn from_board(board: &BoardPanel, entity: EntityType, index: usize) -> Option<Entity> {
+        Some(match entity {
+            EntityType::Outline => Entity::Outline(board.outline.clone()),
+            EntityType::OtherOutline => {
+                Entity::OtherOutline(board.other_outlines.get(index)?.clone())
+            }
+            EntityType::RoutingOutline => {
+                Entity::RoutingOutline(board.routing_outlines.get(index)?.clone())
+            }
+            EntityType::PlacementOutline => {
+                Entity::PlacementOutline(board.placement_outlines.get(index)?.clone())
+            }
+            EntityType::RoutingKeepout => {
+                Entity::RoutingKeepout(board.routing_keepouts.get(index)?.clone())
+            }
+            EntityType::ViaKeepout => Entity::ViaKeepout(board.via_keepouts.get(index)?.clone()),
+            EntityType::PlacementKeepout => {
+                Entity::PlacementKeepout(board.placement_keepouts.get(index)?.clone())
+            }
+            EntityType::PlacementGroupArea => {
+                Entity::PlacementGroupArea(board.placement_group_areas.get(index)?.clone())
+            }
+            EntityType::DrilledHole => Entity::DrilledHole(board.drilled_holes.get(index)?.clone()),
+            EntityType::Note => Entity::Note(board.notes.get(index)?.clone()),
+            EntityType::Placement => {
+                Entity::Placement(board.component_placements.get(index)?.clone())
+            }
+        })
+    }
+
+    /// A short description for conflict messages.
+    fn describe(&self) -> String {
+        let id = match self {
+            Entity::Outline(_) => String::new(),
+            Entity::OtherOutline(o) => o.id.clone(),
+            Entity::PlacementGroupArea(a) => a.group_name.clone(),
+            Entity::DrilledHole(h) => format!(
+                "{} {} at ({}, {})",
+                h.associated_part, h.hole_type, h.x, h.y
+            ),
+            Entity::Note(n) => n.text.clone(),
+            Entity::Placement(p) => p.reference_designator.clone(),
+            Entity::RoutingOutline(RoutingOutline { outline, .. })
+            | Entity::PlacementOutline(PlacementOutline { outline, .. })
+            | Entity::RoutingKeepout(RoutingKeepout { outline, .. })
+            | Entity::ViaKeepout(ViaKeepout { outline, .. })
+            | Entity::PlacementKeepout(PlacementKeepout { outline, .. }) => outline
+                .first()
+                .map(|p| format!("at ({}, {})", p.x, p.y))
+                .unwrap_or_default(),
+        };
+        format!("{} {}", self.entity_type().as_str(), id)
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// Evaluate `body` with `list` bound to the list of the board holding entities of the same type
+/// as `entity` and `value` bound to the entity itself. The board outline is not part of a list,
+/// so for it `outline` is evaluated instead with `value` bound to the outline.
+macro_rules! with_list {
+    ($board:expr, $entity:expr, |$list:ident, $value:ident| $body:expr, $outline:expr) => {
+        match $entity {
+            #[allow(unused_variables)]
+            Entity::Outline($value) => $outline,
+            Entity::OtherOutline($value) => {
+                let $list = &mut $board.other_outlines;
+                $body
+            }
+            Entity::RoutingOutline($value) => {
+                let $list = &mut $board.routing_outlines;
+                $body
+            }
+            Entity::PlacementOutline($value) => {
+                let $list = &mut $board.placement_outlines;
+                $body
+            }
+            Entity::RoutingKeepout($value) => {
+                let $list = &mut $board.routing_keepouts;
+                $body
+            }
+            Entity::ViaKeepout($value) => {
+                let $list = &mut $board.via_keepouts;
+                $body
+            }
+            Entity::PlacementKeepout($value) => {
+                let $list = &mut $board.placement_keepouts;
+                $body
+            }
+            Entity::PlacementGroupArea($value) => {
+                let $list = &mut $board.placement_group_areas;
+                $body
+            }
+            Entity::DrilledHole($value) => {
+                let $list = &mut $board.drilled_holes;
+                $body
+            }
+            Entity::Note($value) => {
+                let $list = &mut $board.notes;
+                $body
+            }
+            Entity::Placement($value) => {
+                let $list = &mut $board.component_placements;
+                $body
+            }
+        }
+    };
+}
+
+/// An entity level change to a board.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub enum Operation {
+    Add(Entity),
+    Remove(Entity),
+    /// Replace `old` with `new`.
+    Modify {
+        old: Entity,
+        new: Entity,
+    },
+}
+
+/// A list of proposed changes to a board, which can be reviewed and applied in place of a full
+/// replacement board file.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct Changeset {
+    /// Name of the board the changes apply to.
+    pub board_name: String,
+    /// Units of every entity in the changeset.
+    pub units: String,
+    pub operations: Vec<Operation>,
+}
+
+impl Changeset {
+    /// Build a changeset from the diff of a base board and a proposed revision of it.
+    ///
+    /// Entities are copied from the boards using the indices recorded in the diff, with those of
+    /// the proposed board converted to the units of the base board.
+    ///
+    /// # Example
+    /// ```
+    /// use idf_parser::changeset::Changeset;
+    /// use idf_parser::diff::diff_boards;
+    /// use idf_parser::parse_board_file;
+    ///
+    /// let base = parse_board_file("src/test_files/board.emn").unwrap();
+    /// let mut proposed = base.clone();
+    /// proposed.component_placements[0].x += 100.0;
+    ///
+    /// let changeset = Changeset::from_diff(&base, &proposed, &diff_boards(&base, &proposed, 0.001));
+    /// assert_eq!(changeset.apply(&base).unwrap(), proposed);
+    /// ```
+    pub fn from_diff(base: &BoardPanel, proposed: &BoardPanel, diff: &BoardDiff) -> Changeset {
+        let proposed = transform_board(proposed, &Transform::default(), &base.header.units);
+        let operations = diff
+            .entities
+            .iter()
+            .filter_map(|e| {
+                let old = e.old.and_then(|i| Entity::from_board(base, e.entity, i));
+                let new = e
+                    .new
+                    .and_then(|i| Entity::from_board(&proposed, e.entity, i));
+                match (old, new) {
+                    (Some(old), Some(new)) => Some(Operation::Modify { old, new }),
+                    (Some(old), None) => Some(Operation::Remove(old)),
+                    (None, Some(new)) => Some(Operation::Add(new)),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+        Changeset {
+            board_name: base.header.board_name.clone(),
+            units: base.header.units.clone(),
+            operations,
+        }
+    }
+
+    /// Every reason the changeset cannot be applied to the board, one per conflicting operation.
+    ///
+    /// A changeset conflicts with a board of another name or units, when an entity to be
+    /// removed or modified is not in the board exactly as it was in the base, and when an entity
+    /// to be added is already in the board or reuses a reference designator.
+    pub fn conflicts(&self, board: &BoardPanel) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        if board.header.board_name != self.board_name {
+            conflicts.push(format!(
+                "Changeset is for board {} but was applied to {}.",
+                self.board_name, board.header.board_name
+            ));
+        }
+        if board.header.units != self.units {
+            conflicts.push(format!(
+                "Changeset is in {} but board {} is in {}.",
+                self.units, board.header.board_name, board.header.units
+            ));
+        }
+        // Check each operation against the board with the operations before it applied, so
+        // that for instance a placement can be removed and added again.
+        let mut board = board.clone();
+        for (i, operation) in self.operations.iter().enumerate() {
+            if let Err(e) = apply_operation(&mut board, operation) {
+                conflicts.push(format!("Operation {}: {}", i + 1, e));
+            }
+        }
+        conflicts
+    }
+
+    /// Apply the changeset to a copy of the board.
+    ///
+    /// Nothing is applied if there are any conflicts, in which case the error lists all of
+    /// them, one per line.
+    pub fn apply(&self, board: &BoardPanel) -> Result<BoardPanel, String> {
+        let conflicts = self.conflicts(board);
+        if !conflicts.is_empty() {
+            return Err(conflicts.join("\n"));
+        }
+        let mut board = board.clone();
+        for operation in self.operations.iter() {
+            apply_operation(&mut board, operation)?;
+        }
+        Ok(board)
+    }
+}
+
+fn refdes_in_use(board: &BoardPanel, entity: &Entity) -> Option<String> {
+    let Entity::Placement(placement) = entity else {
+        return None;
+    };
+    let refdes = &placement.reference_designator;
+    (refdes != "NOREFDES"
+        && refdes != "BOARD"
+        && board
+            .component_placements
+            .iter()
+            .any(|p| &p.reference_designator == refdes))
+    .then(|| refdes.clone())
+}
+
+fn apply_operation(board: &mut BoardPanel, operation: &Operation) -> Result<(), String> {
+    match operation {
+        Operation::Add(entity) => {
+            if let Some(refdes) = refdes_in_use(board, entity) {
+                return Err(format!(
+                    "Reference designator {} is already in use.",
+                    refdes
+                ));
+            }
+            with_list!(
+                board,
+                entity,
+                |list, value| {
+                    if list.contains(value) {
+                        return Err(format!("{} is already on the board.", entity.describe()));
+                    }
+                    list.push(value.clone());
+                },
+                return Err("The board outline cannot be added, only modified.".to_string())
+            );
+        }
+        Operation::Remove(entity) => {
+            with_list!(
+                board,
+                entity,
+                |list, value| {
+                    let Some(i) = list.iter().position(|e| e == value) else {
+                        return Err(format!(
+                            "{} to be removed is not on the board or has changed.",
+                            entity.describe()
+                        ));
+                    };
+                    list.remove(i);
+                },
+                return Err("The board outline cannot be removed, only modified.".to_string())
+            );
+        }
+        Operation::Modify { old, new } => {
+            if old.entity_type() != new.entity_type() {
+                return Err(format!(
+                    "Cannot replace a {} with a {}.",
+                    old.entity_type().as_str(),
+                    new.entity_type().as_str()
+                ));
+            }
+            let position = with_list!(
+                board,
+                old,
+                |list, value| list.iter().position(|e| e == value),
+                (board.outline == *value).then_some(0)
+            );
+            let Some(i) = position else {
+                return Err(format!(
+                    "{} to be modified is not on the board or has changed.",
+                    old.describe()
+                ));
+            };
+            // Take the entity out while checking the new reference designator, so that a
+            // placement may keep its own.
+            if let (Entity::Placement(_), Entity::Placement(placement)) = (old, new) {
+                let original = board.component_placements.remove(i);
+                let in_use = refdes_in_use(board, new);
+                board.component_placements.insert(i, original);
+                if in_use.is_some() {
+                    return Err(format!(
+                        "Reference designator {} is already in use.",
+                        placement.reference_designator
+                    ));
+                }
+            }
+            with_list!(
+                board,
+                new,
+                |list, value| list[i] = value.clone(),
+                board.outline = value.clone()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn write_entity(output: &mut String, entity: &Entity) {
+    match entity {
+        Entity::Outline(o) => writer::write_outline(output, "BOARD_OUTLINE", o),
+        Entity::OtherOutline(o) => writer::write_other_outline(output, o),
+        Entity::RoutingOutline(o) => writer::write_routing_outline(output, o),
+        Entity::PlacementOutline(o) => writer::write_placement_outline(output, o),
+        Entity::RoutingKeepout(k) => writer::write_routing_keepout(output, k),
+        Entity::ViaKeepout(k) => writer::write_via_keepout(output, k),
+        Entity::PlacementKeepout(k) => writer::write_placement_keepout(output, k),
+        Entity::PlacementGroupArea(a) => writer::write_placement_group_area(output, a),
+        Entity::DrilledHole(h) => {
+            output.push_str(".DRILLED_HOLES\n");
+            writer::write_hole(output, h);
+            output.push_str(".END_DRILLED_HOLES\n");
+        }
+        Entity::Note(n) => {
+            output.push_str(".NOTES\n");
+            writer::write_note(output, n);
+            output.push_str(".END_NOTES\n");
+        }
+        Entity::Placement(p) => {
+            output.push_str(".PLACEMENT\n");
+            writer::write_placement(output, p);
+            output.push_str(".END_PLACEMENT\n");
+        }
+    }
+}
+
+/// Write a changeset to text.
+///
+/// The format reuses the sections of a board file. A CHANGESET section holding the board name
+/// and units is followed by an ADD, REMOVE or MODIFY section per operation, each containing the
+/// board file section of its entity, or of the old and then the new entity for MODIFY. Holes,
+/// notes and placements are written as a DRILLED_HOLES, NOTES or PLACEMENT section of one.
+///
+/// ```text
+/// .CHANGESET
+/// sample_board THOU
+/// .END_CHANGESET
+/// .REMOVE
+/// .DRILLED_HOLES
+/// 20 2000 2500 PTH U3 VIA ECAD
+/// .END_DRILLED_HOLES
+/// .END_REMOVE
+/// ```
+pub fn write_changeset(changeset: &Changeset) -> String {
+    let mut output = String::new();
+    output.push_str(".CHANGESET\n");
+    output.push_str(&format!("{} {}\n", changeset.board_name, changeset.units));
+    output.push_str(".END_CHANGESET\n");
+    for operation in changeset.operations.iter() {
+        match operation {
+            Operation::Add(entity) => {
+                output.push_str(".ADD\n");
+                write_entity(&mut output, entity);
+                output.push_str(".END_ADD\n");
+            }
+            Operation::Remove(entity) => {
+                output.push_str(".REMOVE\n");
+                write_entity(&mut output, entity);
+                output.push_str(".END_REMOVE\n");
+            }
+            Operation::Modify { old, new } => {
+                output.push_str(".MODIFY\n");
+                write_entity(&mut output, old);
+                write_entity(&mut output, new);
+                output.push_str(".END_MODIFY\n");
+            }
+        }
+    }
+    output
+}
+
+/// Take the only item of a section which must contain exactly one.
+fn single<T>(mut items: Vec<T>) -> Option<T> {
+    (items.len() == 1).then(|| items.remove(0))
+}
+
+fn entity(input: &str) -> IResult<&str, Entity> {
+    alt((
+        parse_board_panel_outline.map(Entity::Outline),
+        parse_other_outline.map(Entity::OtherOutline),
+        parse_routing_outline.map(Entity::RoutingOutline),
+        parse_placement_outline.map(Entity::PlacementOutline),
+        parse_routing_keepout.map(Entity::RoutingKeepout),
+        parse_via_keepout.map(Entity::ViaKeepout),
+        parse_placement_keepout.map(Entity::PlacementKeepout),
+        parse_placement_group_area.map(Entity::PlacementGroupArea),
+        parse_section!("DRILLED_HOLES", drilled_hole).map(Entity::DrilledHole),
+        map_opt(ws(parse_notes_section), |n| single(n).map(Entity::Note)),
+        parse_section!("PLACEMENT", component_placement).map(Entity::Placement),
+    ))
+    .parse(input)
+}
+
+fn operation(input: &str) -> IResult<&str, Operation> {
+    alt((
+        parse_section!("ADD", entity).map(Operation::Add),
+        parse_section!("REMOVE", entity).map(Operation::Remove),
+        parse_section!("MODIFY", (entity, entity)).map(|(old, new)| Operation::Modify { old, new }),
+    ))
+    .parse(input)
+}
+
+/// Parse the text of a changeset written by [`write_changeset`].
+///
+/// # Example
+/// ```
+/// use idf_parser::changeset::{Entity, Operation, parse_changeset};
+///
+/// let input = ".CHANGESET
+/// sample_board THOU
+/// .END_CHANGESET
+/// .REMOVE
+/// .DRILLED_HOLES
+/// 20 2000 2500 PTH U3 VIA ECAD
+/// .END_DRILLED_HOLES
+/// .END_REMOVE";
+///
+/// let changeset = parse_changeset(input).unwrap();
+/// assert!(matches!(changeset.operations[0], Operation::Remove(Entity::DrilledHole(_))));
+/// ```
+pub fn parse_changeset(input: &str) -> Result<Changeset, nom::Err<nom::error::Error<&str>>> {
+    let (remaining, ((board_name, units), operations)) = (
+        parse_section!(
+            "CHANGESET",
+            ws_separated!((is_not(" \t\r\n"), is_not(" \t\r\n")))
+        ),
+        many0(operation),
+    )
+        .parse(input)?;
+
+    // Check if there is any unparsed data remaining
+    if !remaining.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            remaining,
+            nom::error::ErrorKind::Eof,
+        )));
+    }
+    Ok(Changeset {
+        board_name: board_name.to_string(),
+        units: units.to_string(),
+        operations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff_boards;
+    use crate::parse_board_file;
+
+    fn proposed(base: &BoardPanel) -> BoardPanel {
+        let mut proposed = base.clone();
+        proposed.component_placements[0].rotation_angle = 90.0;
+        proposed.component_placements.remove(1);
+        proposed.drilled_holes[3].diameter = 25.0;
+        proposed.placement_keepouts[0].keepout_height = 100.0;
+        proposed.notes.push(Note {
+            x: 100.0,
+            y: 200.0,
+            text_height: 50.0,
+            test_string_physical_length: 600.0,
+            text: "Check enclosure clearance".to_string(),
+        });
+        proposed
+    }
+
+    #[test]
+    fn test_apply_changeset() {
+        let base = parse_board_file("src/test_files/board.emn").unwrap();
+        let proposed = proposed(&base);
+        let changeset =
+            Changeset::from_diff(&base, &proposed, &diff_boards(&base, &proposed, 0.001));
+        assert_eq!(changeset.operations.len(), 5);
+
+        let applied = changeset.apply(&base).unwrap();
+        assert!(diff_boards(&applied, &proposed, 0.001).is_empty());
+
+        // Applying it twice conflicts, since the removed placement is no longer there.
+        let error = changeset.apply(&applied).unwrap_err();
+        assert!(error.contains("to be removed is not on the board"));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let base = parse_board_file("src/test_files/board.emn").unwrap();
+        let proposed = proposed(&base);
+        let changeset =
+            Changeset::from_diff(&base, &proposed, &diff_boards(&base, &proposed, 0.001));
+
+        // ECAD moved the keepout which MCAD wants to make taller in the meantime.
+        let mut current = base.clone();
+        current.placement_keepouts[0].outline[0].x += 10.0;
+        current.header.units = "MM".to_string();
+        let conflicts = changeset.conflicts(&current);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts[0].contains("is in MM"));
+        assert!(conflicts[1].contains("placement keepout"));
+
+        let mut add = Changeset {
+            operations: vec![Operation::Add(Entity::Placement(
+                base.component_placements[0].clone(),
+            ))],
+            ..changeset
+        };
+        assert!(
+            add.apply(&base)
+                .unwrap_err()
+                .contains("C1 is already in use")
+        );
+        add.board_name = "other_board".to_string();
+        assert_eq!(add.conflicts(&base).len(), 2);
+    }
+
+    #[test]
+    fn test_changeset_round_trip() {
+        let base = parse_board_file("src/test_files/board.emn").unwrap();
+        let proposed = proposed(&base);
+        let changeset =
+            Changeset::from_diff(&base, &proposed, &diff_boards(&base, &proposed, 0.001));
+        let text = write_changeset(&changeset);
+        assert_eq!(parse_changeset(&text).unwrap(), changeset);
+    }
+}
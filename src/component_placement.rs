@@ -1,6 +1,6 @@
 use nom::branch::alt;
 
-use crate::primitives::ws;
+use crate::primitives::{quote_string, ws};
 use crate::{parse_section, ws_separated};
 use nom::IResult;
 use nom::Parser;
@@ -12,6 +12,8 @@ use nom::sequence::delimited;
 
 /// Represents a component placement in the IDF format.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ComponentPlacement {
     pub package_name: String,
     pub part_number: String,
@@ -50,7 +52,7 @@ pub fn component_placement(input: &str) -> IResult<&str, ComponentPlacement> {
         ),
     ) = ws_separated!((
         is_not(" "),                                                      // package name
-        is_not(" "),                                                      // part number
+        alt((quote_string, is_not(" "))),                                 // part number
         not_line_ending,                                                  // reference designator
         float,                                                            // x coordinate
         float,                                                            // y coordinate
@@ -182,10 +184,26 @@ dip_14w pn-hs346-dip U4
         assert_eq!(component_placements, expected);
     }
     #[test]
+    fn test_component_placement_quoted_part_number() {
+        let input = "402  \"CAP_0.1UF_X7R_16V_10% 0402 _402\"  C166\n1585.00 1800.00 0.0 180.000 BOTTOM ECAD";
+        let (_, component_placement) = component_placement(input).unwrap();
+        assert_eq!(
+            component_placement.part_number,
+            "CAP_0.1UF_X7R_16V_10% 0402 _402"
+        );
+        assert_eq!(component_placement.reference_designator, "C166");
+
+        // Quotes are removed from a part number without spaces too.
+        let input = "cs13_a \"pn-cap\" C1\n4000.0 1000.0 100.0 0.0 TOP PLACED";
+        let (_, unspaced) = super::component_placement(input).unwrap();
+        assert_eq!(unspaced.part_number, "pn-cap");
+        assert_eq!(unspaced.reference_designator, "C1");
+    }
+    #[test]
     fn test_invalid_component_placement() {
         // Invalid input with extra text
         let input = "cs13_a pn-cap C1\n4000.0 1000.0 100.0 hi hi 0.0 TOP PLACED\n";
         let result = component_placement(input);
-        assert!(!result.is_ok());
+        assert!(result.is_err());
     }
 }
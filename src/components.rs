@@ -38,23 +38,41 @@ fn electrical_property(input: &str) -> IResult<&str, (String, f32)> {
 }
 
 /// Represent properties of an electrical component.
-type ElectricalProperties = HashMap<String, f32>;
+pub type ElectricalProperties = HashMap<String, f32>;
+
+/// Serialize electrical properties in key order, so that the output is the same every time.
+#[cfg(feature = "serde")]
+fn serialize_properties<S: serde::Serializer>(
+    properties: &ElectricalProperties,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::Serialize;
+    properties
+        .iter()
+        .collect::<std::collections::BTreeMap<_, _>>()
+        .serialize(serializer)
+}
 
 /// Represents an electrical component in the IDF format.
 /// http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=31
 #[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ElectricalComponent {
     pub geometry_name: String,
     pub part_number: String,
     pub units: String,
     pub height: f32,
     pub outline: Vec<Point>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_properties"))]
     pub properties: ElectricalProperties,
 }
 
 /// Represents a mechanical component in the IDF format.
 /// http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=34
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MechanicalComponent {
     pub geometry_name: String,
     pub part_number: String,
@@ -63,6 +81,89 @@ pub struct MechanicalComponent {
     pub outline: Vec<Point>,
 }
 
+/// Whether a library component is electrical or mechanical.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ComponentKind {
+    Electrical,
+    Mechanical,
+}
+
+/// A library component, either electrical or mechanical.
+///
+/// This gives access to the fields shared by both kinds of component, while the electrical only
+/// properties remain accessible through [`Component::properties`] or by matching on the variant.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Component<'a> {
+    Electrical(&'a ElectricalComponent),
+    Mechanical(&'a MechanicalComponent),
+}
+
+impl<'a> Component<'a> {
+    pub fn kind(&self) -> ComponentKind {
+        match self {
+            Component::Electrical(_) => ComponentKind::Electrical,
+            Component::Mechanical(_) => ComponentKind::Mechanical,
+        }
+    }
+
+    pub fn geometry_name(&self) -> &'a str {
+        match self {
+            Component::Electrical(c) => &c.geometry_name,
+            Component::Mechanical(c) => &c.geometry_name,
+        }
+    }
+
+    pub fn part_number(&self) -> &'a str {
+        match self {
+            Component::Electrical(c) => &c.part_number,
+            Component::Mechanical(c) => &c.part_number,
+        }
+    }
+
+    pub fn units(&self) -> &'a str {
+        match self {
+            Component::Electrical(c) => &c.units,
+            Component::Mechanical(c) => &c.units,
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        match self {
+            Component::Electrical(c) => c.height,
+            Component::Mechanical(c) => c.height,
+        }
+    }
+
+    pub fn outline(&self) -> &'a [Point] {
+        match self {
+            Component::Electrical(c) => &c.outline,
+            Component::Mechanical(c) => &c.outline,
+        }
+    }
+
+    /// The properties of an electrical component, None for mechanical components.
+    pub fn properties(&self) -> Option<&'a ElectricalProperties> {
+        match self {
+            Component::Electrical(c) => Some(&c.properties),
+            Component::Mechanical(_) => None,
+        }
+    }
+
+    pub fn as_electrical(&self) -> Option<&'a ElectricalComponent> {
+        match self {
+            Component::Electrical(c) => Some(c),
+            Component::Mechanical(_) => None,
+        }
+    }
+
+    pub fn as_mechanical(&self) -> Option<&'a MechanicalComponent> {
+        match self {
+            Component::Electrical(_) => None,
+            Component::Mechanical(c) => Some(c),
+        }
+    }
+}
+
 /// Parses an electrical component from the input string.
 /// http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=31
 ///
@@ -129,11 +230,11 @@ pub fn mechanical_component(input: &str) -> IResult<&str, MechanicalComponent> {
     let (remaining, (geometry_name, part_number, units, height, outline)) = parse_section!(
         "MECHANICAL",
         ws_separated!((
-            is_not(" "),      // geometry name
-            is_not(" "),      // part number
-            is_not(" "),      // units
-            float,            // height
-            many0(ws(point))  // outline
+            is_not(" "),                      // geometry name
+            alt((quote_string, is_not(" "))), // part number
+            is_not(" "),                      // units
+            float,                            // height
+            many0(ws(point))                  // outline
         ))
     )
     .parse(input)?;
@@ -262,4 +363,19 @@ cs13_a pn-cap THOU 150.0
             }
         );
     }
+    #[test]
+    fn test_mechanical_component_quoted_part_number() {
+        let input = ".MECHANICAL
+bracket \"BRKT 10% NARROW\" MM 5.0
+0 0.0 0.0 0.0
+0 10.0 0.0 0.0
+0 10.0 4.0 0.0
+0 0.0 0.0 0.0
+.END_MECHANICAL";
+        let (remaining, component) = mechanical_component(input).unwrap();
+        assert_eq!(remaining, "");
+        assert_eq!(component.part_number, "BRKT 10% NARROW");
+        assert_eq!(component.units, "MM");
+        assert_eq!(component.outline.len(), 4);
+    }
 }
@@ -0,0 +1,1206 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::components::{Component, ComponentKind, ElectricalProperties};
+use crate::drilled_holes::Hole;
+use crate::flatten::transform_board;
+use crate::geometry::{Polygon, Transform, mm_per_unit};
+use crate::library::Library;
+use crate::point::Point;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The kind of entity a difference refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum EntityType {
+    Outline,
+    OtherOutline,
+    RoutingOutline,
+    PlacementOutline,
+    RoutingKeepout,
+    ViaKeepout,
+    PlacementKeepout,
+    PlacementGroupArea,
+    DrilledHole,
+    Note,
+    Placement,
+}
+
+impl EntityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityType::Outline => "outline",
+            EntityType::OtherOutline => "other outline",
+            EntityType::RoutingOutline => "routing outline",
+            EntityType::PlacementOutline => "placement outline",
+            EntityType::RoutingKeepout => "routing keepout",
+            EntityType::ViaKeepout => "via keepout",
+            EntityType::PlacementKeepout => "placement keepout",
+            EntityType::PlacementGroupArea => "placement group area",
+            EntityType::DrilledHole => "hole",
+            EntityType::Note => "note",
+            EntityType::Placement => "placement",
+        }
+    }
+}
+
+/// A single difference to an entity. Lengths are in millimetres and angles in degrees.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub enum Change {
+    Added,
+    Removed,
+    Moved {
+        dx: f32,
+        dy: f32,
+    },
+    Rotated {
+        from: f32,
+        to: f32,
+    },
+    SideChanged {
+        from: String,
+        to: String,
+    },
+    /// A numeric property such as a diameter, height or area changed.
+    Resized {
+        quantity: &'static str,
+        from: f32,
+        to: f32,
+    },
+    /// A text property such as a part number or owner changed.
+    Modified {
+        field: &'static str,
+        from: String,
+        to: String,
+    },
+    /// An electrical property was added, removed or changed its value.
+    PropertyChanged {
+        key: String,
+        from: Option<f32>,
+        to: Option<f32>,
+    },
+}
+
+impl Change {
+    /// Describe the change, e.g. "moved 2.3 mm" or "rotated 90°".
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Added => "added".to_string(),
+            Change::Removed => "removed".to_string(),
+            Change::Moved { dx, dy } => {
+                format!(
+                    "moved {} mm ({}, {})",
+                    round((dx * dx + dy * dy).sqrt()),
+                    round(*dx),
+                    round(*dy)
+                )
+            }
+            Change::Rotated { from, to } => {
+                format!(
+                    "rotated {}° ({}° to {}°)",
+                    round(rotation_delta(*from, *to)),
+                    from,
+                    to
+                )
+            }
+            Change::SideChanged { from, to } => format!("flipped from {} to {}", from, to),
+            Change::Resized { quantity, from, to } => format!(
+                "{} changed by {} ({} to {})",
+                quantity,
+                round(to - from),
+                round(*from),
+                round(*to)
+            ),
+            Change::Modified { field, from, to } => {
+                format!("{} changed from {} to {}", field, from, to)
+            }
+            Change::PropertyChanged { key, from, to } => match (from, to) {
+                (None, Some(to)) => format!("property {} added ({})", key, to),
+                (Some(from), None) => format!("property {} removed ({})", key, from),
+                (from, to) => format!(
+                    "property {} changed from {} to {}",
+                    key,
+                    from.unwrap_or_default(),
+                    to.unwrap_or_default()
+                ),
+            },
+        }
+    }
+}
+
+/// The differences to one entity.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct EntityDiff {
+    pub entity: EntityType,
+    /// Identifies the entity, e.g. a reference designator, outline id or hole position.
+    pub id: String,
+    /// Index of the entity in its list in the old board, None if it was added.
+    pub old: Option<usize>,
+    /// Index of the entity in its list in the new board, None if it was removed.
+    pub new: Option<usize>,
+    pub changes: Vec<Change>,
+}
+
+/// The semantic differences between two revisions of a board or panel.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct BoardDiff {
+    pub entities: Vec<EntityDiff>,
+}
+
+impl BoardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// The differences of a placement, by reference designator.
+    pub fn placement(&self, refdes: &str) -> Option<&EntityDiff> {
+        self.entities
+            .iter()
+            .find(|e| e.entity == EntityType::Placement && e.id == refdes)
+    }
+
+    /// One line per changed entity, e.g. "placement U5 moved 2.3 mm (2.3, 0), rotated 90° (0° to
+    /// 90°)".
+    pub fn to_text(&self) -> String {
+        self.entities
+            .iter()
+            .map(|e| {
+                let changes: Vec<String> = e.changes.iter().map(|c| c.describe()).collect();
+                format!("{} {} {}\n", e.entity.as_str(), e.id, changes.join(", "))
+            })
+            .collect()
+    }
+}
+
+/// Round to 4 decimal places for display.
+fn round(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4
+}
+
+/// The signed rotation from `from` to `to` in degrees, in (-180, 180].
+fn rotation_delta(from: f32, to: f32) -> f32 {
+    let delta = (to - from).rem_euclid(360.0);
+    if delta > 180.0 { delta - 360.0 } else { delta }
+}
+
+fn close(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+/// An outline or keepout reduced to the parts which are compared.
+struct Shape<'a> {
+    entity: EntityType,
+    /// Index of the entity in its list in the board.
+    index: usize,
+    /// An identifier from the file, such as an other outline id or group name.
+    name: Option<&'a str>,
+    owner: &'a str,
+    side: Option<&'a str>,
+    height: Option<(&'static str, f32)>,
+    points: &'a [Point],
+}
+
+impl Shape<'_> {
+    fn id(&self) -> String {
+        match (self.name, self.points.first()) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(p)) => format!("at ({}, {})", round(p.x), round(p.y)),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn same_points(&self, other: &Shape, dx: f32, dy: f32, tolerance: f32) -> bool {
+        self.points.len() == other.points.len()
+            && self.points.iter().zip(other.points).all(|(a, b)| {
+                a.loop_label == b.loop_label
+                    && close(a.x + dx, b.x, tolerance)
+                    && close(a.y + dy, b.y, tolerance)
+                    && close(a.angle, b.angle, 1e-3)
+            })
+    }
+
+    /// The translation taking this shape onto the other, if it is the same shape.
+    fn translation_to(&self, other: &Shape, tolerance: f32) -> Option<(f32, f32)> {
+        let (a, b) = (self.points.first()?, other.points.first()?);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        self.same_points(other, dx, dy, tolerance)
+            .then_some((dx, dy))
+    }
+
+    fn equals(&self, other: &Shape, tolerance: f32) -> bool {
+        self.name == other.name
+            && self.owner == other.owner
+            && self.side == other.side
+            && match (self.height, other.height) {
+                (Some(a), Some(b)) => close(a.1, b.1, tolerance),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && self.same_points(other, 0.0, 0.0, tolerance)
+    }
+}
+
+fn shapes(board: &BoardPanel) -> Vec<Shape<'_>> {
+    let mut shapes = Vec::new();
+    for (index, o) in board.other_outlines.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::OtherOutline,
+            name: Some(&o.id),
+            owner: &o.owner,
+            side: Some(&o.board_side),
+            height: Some(("extrude thickness", o.extrude_thickness)),
+            points: &o.outline,
+        });
+    }
+    for (index, o) in board.routing_outlines.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::RoutingOutline,
+            name: None,
+            owner: &o.owner,
+            side: Some(&o.routing_layers),
+            height: None,
+            points: &o.outline,
+        });
+    }
+    for (index, o) in board.placement_outlines.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::PlacementOutline,
+            name: None,
+            owner: &o.owner,
+            side: Some(&o.board_side),
+            height: Some(("height", o.outline_height)),
+            points: &o.outline,
+        });
+    }
+    for (index, k) in board.routing_keepouts.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::RoutingKeepout,
+            name: None,
+            owner: &k.owner,
+            side: Some(&k.routing_layers),
+            height: None,
+            points: &k.outline,
+        });
+    }
+    for (index, k) in board.via_keepouts.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::ViaKeepout,
+            name: None,
+            owner: &k.owner,
+            side: None,
+            height: None,
+            points: &k.outline,
+        });
+    }
+    for (index, k) in board.placement_keepouts.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::PlacementKeepout,
+            name: None,
+            owner: &k.owner,
+            side: Some(&k.board_side),
+            height: Some(("height", k.keepout_height)),
+            points: &k.outline,
+        });
+    }
+    for (index, a) in board.placement_group_areas.iter().enumerate() {
+        shapes.push(Shape {
+            index,
+            entity: EntityType::PlacementGroupArea,
+            name: Some(&a.group_name),
+            owner: &a.owner,
+            side: Some(&a.board_side),
+            height: None,
+            points: &a.outline,
+        });
+    }
+    shapes
+}
+
+/// The changes between two shapes which are known to be the same entity.
+fn shape_changes(old: &Shape, new: &Shape, tolerance: f32) -> Vec<Change> {
+    let mut changes = Vec::new();
+    match old.translation_to(new, tolerance) {
+        Some((dx, dy)) if !close(dx, 0.0, tolerance) || !close(dy, 0.0, tolerance) => {
+            changes.push(Change::Moved { dx, dy });
+        }
+        Some(_) => {}
+        None => {
+            let (a, b) = (
+                Polygon::from_outline(old.points, tolerance),
+                Polygon::from_outline(new.points, tolerance),
+            );
+            changes.push(Change::Resized {
+                quantity: "area",
+                from: a.area(),
+                to: b.area(),
+            });
+            if let (Some(a), Some(b)) = (a.bounding_box(), b.bounding_box()) {
+                if !close(a.width(), b.width(), tolerance) {
+                    changes.push(Change::Resized {
+                        quantity: "width",
+                        from: a.width(),
+                        to: b.width(),
+                    });
+                }
+                if !close(a.height(), b.height(), tolerance) {
+                    changes.push(Change::Resized {
+                        quantity: "length",
+                        from: a.height(),
+                        to: b.height(),
+                    });
+                }
+            }
+        }
+    }
+    if let (Some(from), Some(to)) = (old.side, new.side)
+        && from != to
+    {
+        changes.push(Change::SideChanged {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+    if let (Some((quantity, from)), Some((_, to))) = (old.height, new.height)
+        && !close(from, to, tolerance)
+    {
+        changes.push(Change::Resized { quantity, from, to });
+    }
+    if old.owner != new.owner {
+        changes.push(Change::Modified {
+            field: "owner",
+            from: old.owner.to_string(),
+            to: new.owner.to_string(),
+        });
+    }
+    changes
+}
+
+/// Pair up the entities of two revisions.
+///
+/// Entities which are equal are removed first. The rest are paired by each matcher in turn, the
+/// first matcher being the strictest. Each pass first looks for a partner among the new entities
+/// with the same key, e.g. the same reference designator or position, and only then compares the
+/// entities left over with each other, so large revisions with few changes pair quickly. Returns
+/// the pairs of indices, followed by the indices of the removed old entities and of the added new
+/// entities.
+#[allow(clippy::type_complexity)]
+pub(crate) fn pair<'a, T, K: Hash + Eq>(
+    old: &'a [T],
+    new: &'a [T],
+    key: impl Fn(&'a T) -> K,
+    equal: impl Fn(&T, &T) -> bool,
+    matchers: &[&dyn Fn(&T, &T) -> bool],
+) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+    let mut buckets: HashMap<K, Vec<usize>> = HashMap::new();
+    for (j, b) in new.iter().enumerate() {
+        buckets.entry(key(b)).or_default().push(j);
+    }
+    let old_keys: Vec<K> = old.iter().map(key).collect();
+    let mut old_used = vec![false; old.len()];
+    let mut new_used = vec![false; new.len()];
+    let mut pairs = Vec::new();
+    let equal: &dyn Fn(&T, &T) -> bool = &equal;
+    for (pass, matcher) in std::iter::once(equal)
+        .chain(matchers.iter().copied())
+        .enumerate()
+    {
+        for same_key in [true, false] {
+            let leftovers: Vec<usize> = match same_key {
+                true => Vec::new(),
+                false => (0..new.len()).filter(|&j| !new_used[j]).collect(),
+            };
+            for (i, a) in old.iter().enumerate() {
+                if old_used[i] {
+                    continue;
+                }
+                let candidates = match same_key {
+                    true => buckets.get(&old_keys[i]).map_or(&[][..], Vec::as_slice),
+                    false => &leftovers,
+                };
+                let found = candidates
+                    .iter()
+                    .find(|&&j| !new_used[j] && matcher(a, &new[j]));
+                if let Some(&j) = found {
+                    old_used[i] = true;
+                    new_used[j] = true;
+                    // The first pass removes equal entities, which are not reported.
+                    if pass > 0 {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+        }
+    }
+    let removed = (0..old.len()).filter(|&i| !old_used[i]).collect();
+    let added = (0..new.len()).filter(|&j| !new_used[j]).collect();
+    (pairs, removed, added)
+}
+
+/// Snap a coordinate to a grid the size of the tolerance, to key entities by their position.
+fn grid(value: f32, tolerance: f32) -> i64 {
+    (value / tolerance.max(f32::EPSILON)).round() as i64
+}
+
+fn diff_shapes(old: &BoardPanel, new: &BoardPanel, tolerance: f32, diff: &mut BoardDiff) {
+    let (old_shapes, new_shapes) = (shapes(old), shapes(new));
+    let same_kind = |a: &Shape, b: &Shape| a.entity == b.entity;
+    let (pairs, removed, added) = pair(
+        &old_shapes,
+        &new_shapes,
+        // Named entities by name, the others by their first point.
+        |s| match s.name {
+            Some(_) => (s.entity, s.name, None),
+            None => (
+                s.entity,
+                None,
+                s.points
+                    .first()
+                    .map(|p| (grid(p.x, tolerance), grid(p.y, tolerance))),
+            ),
+        },
+        |a, b| same_kind(a, b) && a.equals(b, tolerance),
+        &[
+            // The same named entity, e.g. an other outline id.
+            &|a, b| same_kind(a, b) && a.name.is_some() && a.name == b.name,
+            // The same shape moved.
+            &|a, b| same_kind(a, b) && a.name.is_none() && a.translation_to(b, tolerance).is_some(),
+            // The same shape in place with a different side, height or owner.
+            &|a, b| same_kind(a, b) && a.name.is_none() && a.same_points(b, 0.0, 0.0, tolerance),
+            // A reshaped entity overlapping its old position.
+            &|a, b| {
+                same_kind(a, b)
+                    && a.name.is_none()
+                    && Polygon::from_outline(a.points, tolerance)
+                        .overlaps(&Polygon::from_outline(b.points, tolerance))
+            },
+        ],
+    );
+    for (i, j) in pairs {
+        diff.entities.push(EntityDiff {
+            entity: new_shapes[j].entity,
+            id: old_shapes[i].id(),
+            old: Some(old_shapes[i].index),
+            new: Some(new_shapes[j].index),
+            changes: shape_changes(&old_shapes[i], &new_shapes[j], tolerance),
+        });
+    }
+    for i in removed {
+        diff.entities.push(EntityDiff {
+            entity: old_shapes[i].entity,
+            id: old_shapes[i].id(),
+            old: Some(old_shapes[i].index),
+            new: None,
+            changes: vec![Change::Removed],
+        });
+    }
+    for j in added {
+        diff.entities.push(EntityDiff {
+            entity: new_shapes[j].entity,
+            id: new_shapes[j].id(),
+            old: None,
+            new: Some(new_shapes[j].index),
+            changes: vec![Change::Added],
+        });
+    }
+}
+
+fn hole_id(hole: &Hole) -> String {
+    format!(
+        "{} {} at ({}, {})",
+        hole.associated_part,
+        hole.hole_type,
+        round(hole.x),
+        round(hole.y)
+    )
+}
+
+fn diff_holes(old: &[Hole], new: &[Hole], tolerance: f32, diff: &mut BoardDiff) {
+    let same_position =
+        |a: &Hole, b: &Hole| close(a.x, b.x, tolerance) && close(a.y, b.y, tolerance);
+    let same_hole = |a: &Hole, b: &Hole| {
+        close(a.diameter, b.diameter, tolerance)
+            && a.plating_style == b.plating_style
+            && a.associated_part == b.associated_part
+            && a.hole_type == b.hole_type
+    };
+    let (pairs, removed, added) = pair(
+        old,
+        new,
+        |h| (grid(h.x, tolerance), grid(h.y, tolerance)),
+        |a, b| same_position(a, b) && same_hole(a, b) && a.owner == b.owner,
+        &[&same_position, &same_hole],
+    );
+
+    for (i, j) in pairs {
+        let (a, b) = (&old[i], &new[j]);
+        let mut changes = Vec::new();
+        if !same_position(a, b) {
+            changes.push(Change::Moved {
+                dx: b.x - a.x,
+                dy: b.y - a.y,
+            });
+        }
+        if !close(a.diameter, b.diameter, tolerance) {
+            changes.push(Change::Resized {
+                quantity: "diameter",
+                from: a.diameter,
+                to: b.diameter,
+            });
+        }
+        for (field, from, to) in [
+            ("plating", &a.plating_style, &b.plating_style),
+            ("associated part", &a.associated_part, &b.associated_part),
+            ("hole type", &a.hole_type, &b.hole_type),
+            ("owner", &a.owner, &b.owner),
+        ] {
+            if from != to {
+                changes.push(Change::Modified {
+                    field,
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+        diff.entities.push(EntityDiff {
+            entity: EntityType::DrilledHole,
+            id: hole_id(a),
+            old: Some(i),
+            new: Some(j),
+            changes,
+        });
+    }
+    for i in removed {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::DrilledHole,
+            id: hole_id(&old[i]),
+            old: Some(i),
+            new: None,
+            changes: vec![Change::Removed],
+        });
+    }
+    for j in added {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::DrilledHole,
+            id: hole_id(&new[j]),
+            old: None,
+            new: Some(j),
+            changes: vec![Change::Added],
+        });
+    }
+}
+
+fn placement_changes(
+    a: &ComponentPlacement,
+    b: &ComponentPlacement,
+    tolerance: f32,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if !close(a.x, b.x, tolerance) || !close(a.y, b.y, tolerance) {
+        changes.push(Change::Moved {
+            dx: b.x - a.x,
+            dy: b.y - a.y,
+        });
+    }
+    if !close(
+        rotation_delta(a.rotation_angle, b.rotation_angle),
+        0.0,
+        1e-3,
+    ) {
+        changes.push(Change::Rotated {
+            from: a.rotation_angle,
+            to: b.rotation_angle,
+        });
+    }
+    if a.board_side != b.board_side {
+        changes.push(Change::SideChanged {
+            from: a.board_side.clone(),
+            to: b.board_side.clone(),
+        });
+    }
+    if !close(a.mounting_offset, b.mounting_offset, tolerance) {
+        changes.push(Change::Resized {
+            quantity: "mounting offset",
+            from: a.mounting_offset,
+            to: b.mounting_offset,
+        });
+    }
+    for (field, from, to) in [
+        ("package", &a.package_name, &b.package_name),
+        ("part number", &a.part_number, &b.part_number),
+        ("placement status", &a.placement_status, &b.placement_status),
+    ] {
+        if from != to {
+            changes.push(Change::Modified {
+                field,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn diff_placements(
+    old: &[ComponentPlacement],
+    new: &[ComponentPlacement],
+    tolerance: f32,
+    diff: &mut BoardDiff,
+) {
+    let unique = |p: &ComponentPlacement| {
+        p.reference_designator != "NOREFDES" && p.reference_designator != "BOARD"
+    };
+    let (pairs, removed, added) = pair(
+        old,
+        new,
+        |p| p.reference_designator.as_str(),
+        |a, b| {
+            a.reference_designator == b.reference_designator
+                && placement_changes(a, b, tolerance).is_empty()
+        },
+        &[
+            &|a, b| unique(a) && a.reference_designator == b.reference_designator,
+            // Placements without a unique reference designator are matched by package.
+            &|a, b| {
+                !unique(a)
+                    && a.reference_designator == b.reference_designator
+                    && a.package_name == b.package_name
+                    && a.part_number == b.part_number
+            },
+        ],
+    );
+    for (i, j) in pairs {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Placement,
+            id: old[i].reference_designator.clone(),
+            old: Some(i),
+            new: Some(j),
+            changes: placement_changes(&old[i], &new[j], tolerance),
+        });
+    }
+    for i in removed {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Placement,
+            id: old[i].reference_designator.clone(),
+            old: Some(i),
+            new: None,
+            changes: vec![Change::Removed],
+        });
+    }
+    for j in added {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Placement,
+            id: new[j].reference_designator.clone(),
+            old: None,
+            new: Some(j),
+            changes: vec![Change::Added],
+        });
+    }
+}
+
+fn diff_notes(old: &BoardPanel, new: &BoardPanel, tolerance: f32, diff: &mut BoardDiff) {
+    let (pairs, removed, added) = pair(
+        &old.notes,
+        &new.notes,
+        |n| n.text.as_str(),
+        |a, b| {
+            a.text == b.text
+                && close(a.x, b.x, tolerance)
+                && close(a.y, b.y, tolerance)
+                && close(a.text_height, b.text_height, tolerance)
+                && close(
+                    a.test_string_physical_length,
+                    b.test_string_physical_length,
+                    tolerance,
+                )
+        },
+        &[&|a, b| a.text == b.text],
+    );
+    for (i, j) in pairs {
+        let (a, b) = (&old.notes[i], &new.notes[j]);
+        let mut changes = Vec::new();
+        if !close(a.x, b.x, tolerance) || !close(a.y, b.y, tolerance) {
+            changes.push(Change::Moved {
+                dx: b.x - a.x,
+                dy: b.y - a.y,
+            });
+        }
+        if !close(a.text_height, b.text_height, tolerance) {
+            changes.push(Change::Resized {
+                quantity: "text height",
+                from: a.text_height,
+                to: b.text_height,
+            });
+        }
+        if !close(
+            a.test_string_physical_length,
+            b.test_string_physical_length,
+            tolerance,
+        ) {
+            changes.push(Change::Resized {
+                quantity: "text length",
+                from: a.test_string_physical_length,
+                to: b.test_string_physical_length,
+            });
+        }
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Note,
+            id: a.text.clone(),
+            old: Some(i),
+            new: Some(j),
+            changes,
+        });
+    }
+    for i in removed {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Note,
+            id: old.notes[i].text.clone(),
+            old: Some(i),
+            new: None,
+            changes: vec![Change::Removed],
+        });
+    }
+    for j in added {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Note,
+            id: new.notes[j].text.clone(),
+            old: None,
+            new: Some(j),
+            changes: vec![Change::Added],
+        });
+    }
+}
+
+/// Compare two revisions of a board or panel.
+///
+/// Entities are matched semantically rather than by their order in the file: placements by
+/// reference designator, holes by position and diameter, and outlines and keepouts by their
+/// geometry. Both boards are converted to millimetres first, so all deltas are in millimetres
+/// and a change of units alone is not a difference. Values within `tolerance` millimetres of
+/// each other are treated as equal.
+///
+/// # Example
+/// ```
+/// use idf_parser::diff::diff_boards;
+/// use idf_parser::parse_board_file;
+///
+/// let old = parse_board_file("src/test_files/board.emn").unwrap();
+/// let mut new = old.clone();
+/// new.component_placements.reverse();
+/// new.component_placements[0].rotation_angle += 90.0;
+///
+/// let diff = diff_boards(&old, &new, 0.001);
+/// assert_eq!(diff.entities.len(), 1);
+/// assert!(diff.to_text().contains("rotated 90°"));
+/// ```
+pub fn diff_boards(old: &BoardPanel, new: &BoardPanel, tolerance: f32) -> BoardDiff {
+    let old = transform_board(old, &Transform::default(), "MM");
+    let new = transform_board(new, &Transform::default(), "MM");
+    let mut diff = BoardDiff::default();
+
+    let old_outline = Shape {
+        entity: EntityType::Outline,
+        index: 0,
+        name: None,
+        owner: &old.outline.owner,
+        side: None,
+        height: Some(("thickness", old.outline.thickness)),
+        points: &old.outline.outline,
+    };
+    let new_outline = Shape {
+        owner: &new.outline.owner,
+        height: Some(("thickness", new.outline.thickness)),
+        points: &new.outline.outline,
+        ..old_outline
+    };
+    if !old_outline.equals(&new_outline, tolerance) {
+        diff.entities.push(EntityDiff {
+            entity: EntityType::Outline,
+            id: new.header.board_name.clone(),
+            old: Some(0),
+            new: Some(0),
+            changes: shape_changes(&old_outline, &new_outline, tolerance),
+        });
+    }
+
+    diff_shapes(&old, &new, tolerance, &mut diff);
+    diff_holes(&old.drilled_holes, &new.drilled_holes, tolerance, &mut diff);
+    diff_notes(&old, &new, tolerance, &mut diff);
+    diff_placements(
+        &old.component_placements,
+        &new.component_placements,
+        tolerance,
+        &mut diff,
+    );
+    diff
+}
+
+/// The differences to one library component.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct ComponentDiff {
+    pub geometry_name: String,
+    /// The part number in the old library, or in the new library if the component was added.
+    pub part_number: String,
+    pub changes: Vec<Change>,
+}
+
+/// The differences between two revisions of a component library.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct LibraryDiff {
+    pub components: Vec<ComponentDiff>,
+}
+
+impl LibraryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// The differences of a component, by geometry name.
+    pub fn component(&self, geometry_name: &str) -> Option<&ComponentDiff> {
+        self.components
+            .iter()
+            .find(|c| c.geometry_name == geometry_name)
+    }
+
+    /// One line per changed component, e.g. "CAP_0402 (C-0402-100N) height changed by 0.2 (0.5
+    /// to 0.7)".
+    pub fn to_text(&self) -> String {
+        self.components
+            .iter()
+            .map(|c| {
+                let changes: Vec<String> = c.changes.iter().map(|c| c.describe()).collect();
+                format!(
+                    "{} ({}) {}\n",
+                    c.geometry_name,
+                    c.part_number,
+                    changes.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+/// A library component with its height and outline in millimetres.
+struct MetricComponent<'a> {
+    kind: ComponentKind,
+    geometry_name: &'a str,
+    part_number: &'a str,
+    height: f32,
+    outline: Vec<Point>,
+    properties: Option<&'a ElectricalProperties>,
+}
+
+impl<'a> MetricComponent<'a> {
+    fn new(component: Component<'a>) -> Self {
+        let scale = mm_per_unit(component.units());
+        MetricComponent {
+            kind: component.kind(),
+            geometry_name: component.geometry_name(),
+            part_number: component.part_number(),
+            height: component.height() * scale,
+            outline: component
+                .outline()
+                .iter()
+                .map(|p| Point {
+                    x: p.x * scale,
+                    y: p.y * scale,
+                    ..p.clone()
+                })
+                .collect(),
+            properties: component.properties(),
+        }
+    }
+
+    fn shape(&self) -> Shape<'_> {
+        Shape {
+            entity: EntityType::Outline,
+            index: 0,
+            name: None,
+            owner: "",
+            side: None,
+            height: None,
+            points: &self.outline,
+        }
+    }
+}
+
+fn component_changes(a: &MetricComponent, b: &MetricComponent, tolerance: f32) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if a.kind != b.kind {
+        changes.push(Change::Modified {
+            field: "kind",
+            from: format!("{:?}", a.kind),
+            to: format!("{:?}", b.kind),
+        });
+    }
+    if a.part_number != b.part_number {
+        changes.push(Change::Modified {
+            field: "part number",
+            from: a.part_number.to_string(),
+            to: b.part_number.to_string(),
+        });
+    }
+    if !close(a.height, b.height, tolerance) {
+        changes.push(Change::Resized {
+            quantity: "height",
+            from: a.height,
+            to: b.height,
+        });
+    }
+    let (old_shape, new_shape) = (a.shape(), b.shape());
+    if !old_shape.same_points(&new_shape, 0.0, 0.0, tolerance) {
+        changes.extend(shape_changes(&old_shape, &new_shape, tolerance));
+    }
+
+    let empty = ElectricalProperties::new();
+    let (old_properties, new_properties) = (
+        a.properties.unwrap_or(&empty),
+        b.properties.unwrap_or(&empty),
+    );
+    let mut keys: Vec<&String> = old_properties.keys().chain(new_properties.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (from, to) = (
+            old_properties.get(key).copied(),
+            new_properties.get(key).copied(),
+        );
+        if from != to {
+            changes.push(Change::PropertyChanged {
+                key: key.clone(),
+                from,
+                to,
+            });
+        }
+    }
+    changes
+}
+
+/// Compare two revisions of a component library.
+///
+/// Components are matched by geometry name and part number, or by geometry name alone when the
+/// part number changed. Heights and outlines are compared in millimetres, so a change of units
+/// alone is not a difference, and values within `tolerance` millimetres of each other are
+/// treated as equal. Electrical properties are compared per key.
+///
+/// # Example
+/// ```
+/// use idf_parser::diff::{Change, diff_libraries};
+/// use idf_parser::parse_library_file;
+///
+/// let old = parse_library_file("src/test_files/library.emp").unwrap();
+/// let mut new = old.clone();
+/// new.electrical_components[0].height += 10.0;
+///
+/// let diff = diff_libraries(&old, &new, 0.001);
+/// assert!(matches!(diff.components[0].changes[0], Change::Resized { quantity: "height", .. }));
+/// ```
+pub fn diff_libraries(old: &Library, new: &Library, tolerance: f32) -> LibraryDiff {
+    let old_components: Vec<MetricComponent> = old.components().map(MetricComponent::new).collect();
+    let new_components: Vec<MetricComponent> = new.components().map(MetricComponent::new).collect();
+    let same_part = |a: &MetricComponent, b: &MetricComponent| {
+        a.geometry_name == b.geometry_name && a.part_number == b.part_number
+    };
+    let (pairs, removed, added) = pair(
+        &old_components,
+        &new_components,
+        |c| c.geometry_name,
+        |a, b| same_part(a, b) && component_changes(a, b, tolerance).is_empty(),
+        &[&same_part, &|a, b| a.geometry_name == b.geometry_name],
+    );
+
+    let mut diff = LibraryDiff::default();
+    for (i, j) in pairs {
+        let (a, b) = (&old_components[i], &new_components[j]);
+        diff.components.push(ComponentDiff {
+            geometry_name: a.geometry_name.to_string(),
+            part_number: a.part_number.to_string(),
+            changes: component_changes(a, b, tolerance),
+        });
+    }
+    for (components, indices, change) in [
+        (&old_components, removed, Change::Removed),
+        (&new_components, added, Change::Added),
+    ] {
+        for i in indices {
+            diff.components.push(ComponentDiff {
+                geometry_name: components[i].geometry_name.to_string(),
+                part_number: components[i].part_number.to_string(),
+                changes: vec![change.clone()],
+            });
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_board_file;
+
+    fn sample() -> BoardPanel {
+        parse_board_file("src/test_files/board.emn").unwrap()
+    }
+
+    #[test]
+    fn test_reordering_is_not_a_difference() {
+        let old = sample();
+        let mut new = old.clone();
+        new.component_placements.reverse();
+        new.drilled_holes.reverse();
+        new.placement_keepouts.reverse();
+        assert!(diff_boards(&old, &new, 0.001).is_empty());
+
+        // Neither is a change of units.
+        let mut metric = transform_board(&old, &Transform::default(), "MM");
+        metric.header.units = "MM".to_string();
+        assert!(diff_boards(&old, &metric, 0.001).is_empty());
+    }
+
+    #[test]
+    fn test_pairs_across_keys() {
+        let old = sample();
+        let mut new = old.clone();
+        // Within the tolerance, though snapped to another grid position.
+        new.drilled_holes[0].x += 0.006;
+        assert!(diff_boards(&old, &new, 0.01).is_empty());
+
+        // And paired as one resized hole rather than a removal and an addition.
+        new.drilled_holes[0].diameter = 40.0;
+        let diff = diff_boards(&old, &new, 0.01);
+        assert_eq!(diff.entities.len(), 1);
+        assert!(matches!(
+            diff.entities[0].changes[..],
+            [Change::Resized {
+                quantity: "diameter",
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_placement_changes() {
+        let old = sample();
+        let mut new = old.clone();
+        // Move C1 by 100 thou and rotate it, flip C3 and remove C2.
+        new.component_placements[0].x += 100.0;
+        new.component_placements[0].rotation_angle = 270.0;
+        new.component_placements[2].board_side = "TOP".to_string();
+        new.component_placements.remove(1);
+
+        let diff = diff_boards(&old, &new, 0.001);
+        let c1 = diff.placement("C1").unwrap();
+        assert_eq!(c1.changes.len(), 2);
+        let Change::Moved { dx, dy } = c1.changes[0] else {
+            panic!("C1 did not move");
+        };
+        assert!((dx - 2.54).abs() < 1e-4 && dy == 0.0);
+        assert!(c1.changes[1].describe().starts_with("rotated -90°"));
+        assert_eq!(
+            diff.placement("C3").unwrap().changes,
+            [Change::SideChanged {
+                from: "BOTTOM".to_string(),
+                to: "TOP".to_string()
+            }]
+        );
+        assert_eq!(diff.placement("C2").unwrap().changes, [Change::Removed]);
+    }
+
+    #[test]
+    fn test_hole_and_shape_changes() {
+        let old = sample();
+        let mut new = old.clone();
+        new.drilled_holes[0].diameter = 40.0;
+        new.drilled_holes[1].x += 10.0;
+        for point in new.placement_keepouts[1].outline.iter_mut() {
+            point.y += 100.0;
+        }
+        new.placement_keepouts[0].keepout_height = 25.0;
+        new.outline.thickness = 31.0;
+
+        let diff = diff_boards(&old, &new, 0.001);
+        let changes = |entity| -> Vec<&Change> {
+            diff.entities
+                .iter()
+                .filter(|e| e.entity == entity)
+                .flat_map(|e| e.changes.iter())
+                .collect()
+        };
+        let holes = changes(EntityType::DrilledHole);
+        assert_eq!(holes.len(), 2);
+        assert!(holes.iter().any(|c| matches!(
+            c,
+            Change::Resized {
+                quantity: "diameter",
+                ..
+            }
+        )));
+        assert!(holes.iter().any(|c| matches!(c, Change::Moved { .. })));
+
+        let keepouts = changes(EntityType::PlacementKeepout);
+        assert_eq!(keepouts.len(), 2);
+        assert!(
+            keepouts
+                .iter()
+                .any(|c| matches!(c, Change::Moved { dx, .. } if *dx == 0.0))
+        );
+        assert!(keepouts.iter().any(|c| matches!(
+            c,
+            Change::Resized {
+                quantity: "height",
+                ..
+            }
+        )));
+
+        assert!(matches!(
+            changes(EntityType::Outline)[..],
+            [Change::Resized {
+                quantity: "thickness",
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_library_changes() {
+        let old = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        let mut new = old.clone();
+        let c = &mut new.electrical_components[0];
+        c.height = 200.0;
+        c.part_number = "pn-cap-2".to_string();
+        c.properties.insert("CAPACITANCE".to_string(), 47.0);
+        for point in c.outline.iter_mut() {
+            point.x *= 2.0;
+        }
+        new.electrical_components.remove(1);
+
+        let diff = diff_libraries(&old, &new, 0.001);
+        let changes = &diff.component("cs13_a").unwrap().changes;
+        assert_eq!(
+            changes[0],
+            Change::Modified {
+                field: "part number",
+                from: "pn-cap".to_string(),
+                to: "pn-cap-2".to_string()
+            }
+        );
+        let Change::Resized { quantity, from, to } = changes[1] else {
+            panic!("height did not change");
+        };
+        assert_eq!(quantity, "height");
+        assert!((to - from - 50.0 * 0.0254).abs() < 1e-4);
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            Change::Resized { quantity: "width", from, to } if (to - 2.0 * from).abs() < 1e-4
+        )));
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            Change::PropertyChanged { key, to: Some(47.0), .. } if key == "CAPACITANCE"
+        )));
+        assert_eq!(diff.component("cc1210").unwrap().changes, [Change::Removed]);
+        assert_eq!(diff.components.len(), 2);
+    }
+}
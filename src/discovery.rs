@@ -0,0 +1,453 @@
+use crate::assembly::Assembly;
+use crate::board::{BoardPanel, parse_board_or_panel};
+use crate::library::{Library, parse_library};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The type of an IDF file, as given by the first field of its header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum FileType {
+    Board,
+    Panel,
+    Library,
+}
+
+/// Determine the type of an IDF file from its header, regardless of the file name.
+///
+/// # Example
+/// ```
+/// use idf_parser::discovery::{file_type, FileType};
+///
+/// let input = ".HEADER
+/// PANEL_FILE 3.0 \"Sample File Generator\" 10/22/96.16:20:19 1
+/// sample_panel THOU
+/// .END_HEADER";
+/// assert_eq!(file_type(input), Some(FileType::Panel));
+/// ```
+pub fn file_type(input: &str) -> Option<FileType> {
+    let mut tokens = input.split_whitespace();
+    if tokens.next()? != ".HEADER" {
+        return None;
+    }
+    match tokens.next()? {
+        "BOARD_FILE" => Some(FileType::Board),
+        "PANEL_FILE" => Some(FileType::Panel),
+        "LIBRARY_FILE" => Some(FileType::Library),
+        _ => None,
+    }
+}
+
+/// A problem found while discovering an assembly.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub enum DiscoveryIssue {
+    /// The file could not be read, or is not an IDF file.
+    Unrecognised { path: PathBuf, reason: String },
+    /// The file has an IDF header but failed to parse.
+    ParseFailed { path: PathBuf, reason: String },
+    /// More than one board file has the same board name.
+    DuplicateBoardName { name: String, paths: Vec<PathBuf> },
+    /// No library contains the components of this board, so it was left out of the assembly.
+    OrphanedBoard { path: PathBuf },
+    /// The library sharing the board's file stem, or the only library present, is missing some of
+    /// the board's components, so it was not paired with the board on that basis.
+    IncompleteLibrary {
+        board: PathBuf,
+        library: PathBuf,
+        missing: Vec<String>,
+    },
+    /// More than one library could be paired with this board. All of them were included.
+    AmbiguousLibrary {
+        board: PathBuf,
+        libraries: Vec<PathBuf>,
+    },
+    /// The library was not paired with any board and was left out of the assembly.
+    OrphanedLibrary { path: PathBuf },
+    /// More than one panel file was found, so none were used.
+    AmbiguousPanel { paths: Vec<PathBuf> },
+    /// The panel places a board which was not found, so the panel was left out of the assembly.
+    MissingPanelBoard { panel: PathBuf, board_name: String },
+    /// The board is not placed on the panel.
+    UnplacedBoard { path: PathBuf },
+    /// The files which were paired could not be built into an assembly.
+    AssemblyFailed { reason: String },
+}
+
+/// How each file found was classified and used.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct DiscoveryReport {
+    /// Every IDF file found, with its type.
+    pub files: Vec<(PathBuf, FileType)>,
+    /// The panel used in the assembly, if any.
+    pub panel: Option<PathBuf>,
+    /// Each board used in the assembly paired with the libraries providing its components.
+    pub pairs: Vec<(PathBuf, Vec<PathBuf>)>,
+    pub issues: Vec<DiscoveryIssue>,
+}
+
+/// Discover an assembly from all of the IDF files in a directory.
+///
+/// Every file in the directory (not including subdirectories) is read and classified by its header,
+/// files which are not IDF files are skipped unless they have an `.emn` or `.emp` extension.
+/// See [`discover_files`] for how files are paired. Only failing to read the directory is an
+/// error.
+pub fn discover_directory(directory: &Path) -> Result<(Option<Assembly>, DiscoveryReport), String> {
+    let entries = std::fs::read_dir(directory).map_err(|e| e.to_string())?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(discover(&paths, false))
+}
+
+/// Discover an assembly from a list of IDF files.
+///
+/// Files are classified by the `file_type` in their header rather than their extension. Each board
+/// is paired with the library sharing its file stem (e.g. `ISOL.emn` and `ISOL.emp`), otherwise with
+/// the only library present, otherwise with every library containing all of its placed components.
+/// The library sharing the stem, or the only library, is only used if it contains all of the
+/// board's placed components. A single panel is matched to the boards it places by board name.
+///
+/// Problems are recorded in the report rather than returned as errors. The assembly is None if
+/// the files found could not be built into one, for example when no board could be paired, in
+/// which case the report has a [`DiscoveryIssue::AssemblyFailed`] issue giving the reason.
+///
+/// # Example
+/// ```
+/// use idf_parser::discovery::discover_files;
+///
+/// let (assembly, report) = discover_files(&[
+///     "src/test_files/panel.emn",
+///     "src/test_files/board.emn",
+///     "src/test_files/library.emp",
+/// ]);
+/// assert!(assembly.unwrap().panel().is_some());
+/// assert!(report.issues.is_empty());
+/// ```
+pub fn discover_files<P: AsRef<Path>>(paths: &[P]) -> (Option<Assembly>, DiscoveryReport) {
+    let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+    discover(&paths, true)
+}
+
+fn is_idf_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("emn") || e.eq_ignore_ascii_case("emp"))
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn discover(paths: &[PathBuf], explicit: bool) -> (Option<Assembly>, DiscoveryReport) {
+    let mut report = DiscoveryReport::default();
+    let mut boards: Vec<(PathBuf, BoardPanel)> = Vec::new();
+    let mut panels: Vec<(PathBuf, BoardPanel)> = Vec::new();
+    let mut libraries: Vec<(PathBuf, Library)> = Vec::new();
+
+    for path in paths {
+        let report_unrecognised = explicit || is_idf_extension(path);
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                if report_unrecognised {
+                    report.issues.push(DiscoveryIssue::Unrecognised {
+                        path: path.clone(),
+                        reason: e.to_string(),
+                    });
+                }
+                continue;
+            }
+        };
+        let Some(kind) = file_type(&content) else {
+            if report_unrecognised {
+                report.issues.push(DiscoveryIssue::Unrecognised {
+                    path: path.clone(),
+                    reason: "No IDF header found.".to_string(),
+                });
+            }
+            continue;
+        };
+        report.files.push((path.clone(), kind));
+
+        let parse_failed = |reason: String| DiscoveryIssue::ParseFailed {
+            path: path.clone(),
+            reason,
+        };
+        match kind {
+            FileType::Board | FileType::Panel => match parse_board_or_panel(&content) {
+                Ok(board) if kind == FileType::Board => boards.push((path.clone(), board)),
+                Ok(panel) => panels.push((path.clone(), panel)),
+                Err(e) => report.issues.push(parse_failed(e.to_string())),
+            },
+            FileType::Library => match parse_library(&content) {
+                Ok(library) => libraries.push((path.clone(), library)),
+                Err(e) => report.issues.push(parse_failed(e.to_string())),
+            },
+        }
+    }
+
+    // Boards are referred to by name from panels, so names must be unique.
+    let mut by_name: HashMap<&str, Vec<PathBuf>> = HashMap::new();
+    for (path, board) in boards.iter() {
+        by_name
+            .entry(&board.header.board_name)
+            .or_default()
+            .push(path.clone());
+    }
+    let mut duplicates: Vec<_> = by_name.into_iter().filter(|(_, p)| p.len() > 1).collect();
+    duplicates.sort();
+    for (name, paths) in duplicates {
+        report.issues.push(DiscoveryIssue::DuplicateBoardName {
+            name: name.to_string(),
+            paths,
+        });
+    }
+
+    // Pair each board with its libraries.
+    let mut used_libraries = HashSet::new();
+    let mut paired_boards = Vec::new();
+    for (path, board) in boards {
+        let same_stem: Vec<usize> = (0..libraries.len())
+            .filter(|&i| stem(&libraries[i].0) == stem(&path))
+            .collect();
+        let preferred = if same_stem.len() == 1 {
+            Some(same_stem[0])
+        } else if libraries.len() == 1 {
+            Some(0)
+        } else {
+            None
+        };
+        let candidates = match preferred {
+            Some(i) if missing_components(&libraries[i].1, &board).is_empty() => vec![i],
+            _ => {
+                if let Some(i) = preferred {
+                    report.issues.push(DiscoveryIssue::IncompleteLibrary {
+                        board: path.clone(),
+                        library: libraries[i].0.clone(),
+                        missing: missing_components(&libraries[i].1, &board),
+                    });
+                }
+                (0..libraries.len())
+                    .filter(|&i| missing_components(&libraries[i].1, &board).is_empty())
+                    .collect()
+            }
+        };
+
+        match candidates.len() {
+            0 => {
+                report.issues.push(DiscoveryIssue::OrphanedBoard { path });
+                continue;
+            }
+            1 => {}
+            _ => report.issues.push(DiscoveryIssue::AmbiguousLibrary {
+                board: path.clone(),
+                libraries: candidates.iter().map(|&i| libraries[i].0.clone()).collect(),
+            }),
+        }
+        used_libraries.extend(candidates.iter().copied());
+        report.pairs.push((
+            path.clone(),
+            candidates.iter().map(|&i| libraries[i].0.clone()).collect(),
+        ));
+        paired_boards.push((path, board));
+    }
+
+    let mut assembly_libraries = Vec::new();
+    for (i, (path, library)) in libraries.into_iter().enumerate() {
+        if used_libraries.contains(&i) {
+            assembly_libraries.push(library);
+        } else {
+            report.issues.push(DiscoveryIssue::OrphanedLibrary { path });
+        }
+    }
+
+    // Match the panel to its boards.
+    let mut panel = None;
+    if panels.len() > 1 {
+        report.issues.push(DiscoveryIssue::AmbiguousPanel {
+            paths: panels.iter().map(|(p, _)| p.clone()).collect(),
+        });
+    } else if let Some((panel_path, candidate)) = panels.pop() {
+        let placed: HashSet<&str> = candidate
+            .component_placements
+            .iter()
+            .filter(|p| p.reference_designator == "BOARD")
+            .map(|p| p.package_name.as_str())
+            .collect();
+        let mut missing: Vec<&str> = placed
+            .iter()
+            .filter(|name| {
+                !paired_boards
+                    .iter()
+                    .any(|(_, b)| b.header.board_name == **name)
+            })
+            .copied()
+            .collect();
+        missing.sort();
+        for board_name in missing.iter() {
+            report.issues.push(DiscoveryIssue::MissingPanelBoard {
+                panel: panel_path.clone(),
+                board_name: board_name.to_string(),
+            });
+        }
+        for (path, board) in paired_boards.iter() {
+            if !placed.contains(board.header.board_name.as_str()) {
+                report
+                    .issues
+                    .push(DiscoveryIssue::UnplacedBoard { path: path.clone() });
+            }
+        }
+        if missing.is_empty() {
+            report.panel = Some(panel_path);
+            panel = Some(candidate);
+        }
+    }
+
+    let boards = paired_boards.into_iter().map(|(_, b)| b).collect();
+    match Assembly::new(panel, boards, assembly_libraries) {
+        Ok(assembly) => (Some(assembly), report),
+        Err(reason) => {
+            report
+                .issues
+                .push(DiscoveryIssue::AssemblyFailed { reason });
+            (None, report)
+        }
+    }
+}
+
+/// The packages placed on the board which the library does not define, sorted and without
+/// repeats.
+fn missing_components(library: &Library, board: &BoardPanel) -> Vec<String> {
+    let defined: HashSet<&str> = library.components().map(|c| c.geometry_name()).collect();
+    let mut missing: Vec<String> = board
+        .component_placements
+        .iter()
+        .filter(|p| p.reference_designator != "BOARD")
+        .filter(|p| !defined.contains(p.package_name.as_str()))
+        .map(|p| p.package_name.clone())
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_type() {
+        assert_eq!(
+            file_type(".HEADER\nBOARD_FILE 3.0 \"x\" 10/22/96.16:02:44 1\n"),
+            Some(FileType::Board)
+        );
+        assert_eq!(
+            file_type("  .HEADER\r\nLIBRARY_FILE 3.0 x 1 1\r\n"),
+            Some(FileType::Library)
+        );
+        assert_eq!(file_type("not an idf file"), None);
+    }
+
+    #[test]
+    fn test_discover_files_pairs_by_stem() {
+        let (assembly, report) = discover_files(&[
+            "src/test_files/ISOL.emp",
+            "src/test_files/ISOL.emn",
+            "src/test_files/library.emp",
+            "src/test_files/board.emn",
+        ]);
+        let assembly = assembly.unwrap();
+
+        assert_eq!(assembly.boards().len(), 2);
+        assert_eq!(assembly.libraries().len(), 2);
+        assert!(report.issues.is_empty());
+        let isol = report
+            .pairs
+            .iter()
+            .find(|(board, _)| board.ends_with("ISOL.emn"))
+            .unwrap();
+        assert_eq!(isol.1, vec![PathBuf::from("src/test_files/ISOL.emp")]);
+    }
+
+    #[test]
+    fn test_discover_files_reports_problems() {
+        let (assembly, report) = discover_files(&[
+            "src/test_files/invalid_panel.emn",
+            "src/test_files/board.emn",
+            "src/test_files/library.emp",
+            "src/test_files/esp.emp",
+            "README.md",
+        ]);
+        let assembly = assembly.unwrap();
+
+        // The panel references a board which is not present so it is not used.
+        assert!(assembly.panel().is_none());
+        assert!(report.panel.is_none());
+        assert!(report.issues.contains(&DiscoveryIssue::MissingPanelBoard {
+            panel: PathBuf::from("src/test_files/invalid_panel.emn"),
+            board_name: "sample_board_invalid".to_string(),
+        }));
+        assert!(report.issues.contains(&DiscoveryIssue::OrphanedLibrary {
+            path: PathBuf::from("src/test_files/esp.emp")
+        }));
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| matches!(i, DiscoveryIssue::Unrecognised { path, .. } if path.ends_with("README.md")))
+        );
+    }
+
+    #[test]
+    fn test_discover_directory() {
+        let (assembly, report) = discover_directory(Path::new("src/test_files")).unwrap();
+        let assembly = assembly.unwrap();
+
+        // Two panel files are present, so neither can be used.
+        assert!(assembly.panel().is_none());
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| matches!(i, DiscoveryIssue::AmbiguousPanel { paths } if paths.len() == 2))
+        );
+        assert_eq!(assembly.boards().len(), 5);
+        assert_eq!(report.files.len(), 12);
+    }
+
+    #[test]
+    fn test_discover_files_checks_the_only_library() {
+        // The only library does not define the board's components.
+        let (assembly, report) =
+            discover_files(&["src/test_files/board.emn", "src/test_files/esp.emp"]);
+        assert!(assembly.is_none());
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            DiscoveryIssue::IncompleteLibrary { library, missing, .. }
+                if library.ends_with("esp.emp") && missing.contains(&"cs13_a".to_string())
+        )));
+        assert!(report.issues.contains(&DiscoveryIssue::OrphanedBoard {
+            path: PathBuf::from("src/test_files/board.emn")
+        }));
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| matches!(i, DiscoveryIssue::AssemblyFailed { .. }))
+        );
+
+        // A library on its own is reported rather than an error.
+        let (assembly, report) = discover_files(&["src/test_files/esp.emp"]);
+        assert!(assembly.is_none());
+        assert_eq!(report.files.len(), 1);
+        assert!(report.issues.contains(&DiscoveryIssue::OrphanedLibrary {
+            path: PathBuf::from("src/test_files/esp.emp")
+        }));
+    }
+}
@@ -13,6 +13,8 @@ use nom::number::complete::float;
 /// Represents a drilled hole in the IDF format.
 /// http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=25
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Hole {
     pub diameter: f32,
     pub x: f32,                  // absolute x coordinate
@@ -0,0 +1,813 @@
+use crate::board::BoardPanel;
+use crate::components::Component;
+use crate::flatten::transform_board;
+use crate::geometry::{Polygon, Transform, Vertex, is_full_circle, mm_per_unit, split_loops};
+use crate::library::Library;
+use crate::outlines::{BoardPanelOutline, PlacementKeepout, RoutingKeepout, ViaKeepout};
+use crate::point::Point;
+use std::collections::BTreeMap;
+
+/// Layer names and AutoCAD colour indices of the exported entities.
+const LAYERS: [(&str, u8); 12] = [
+    ("BOARD_OUTLINE", 3),
+    ("OTHER_OUTLINE", 30),
+    ("ROUTE_OUTLINE", 5),
+    ("PLACE_OUTLINE", 6),
+    ("ROUTE_KEEPOUT", 150),
+    ("VIA_KEEPOUT", 40),
+    ("PLACE_KEEPOUT", 1),
+    ("PLACE_REGION", 4),
+    ("DRILLED_HOLES", 7),
+    ("COMPONENTS_TOP", 8),
+    ("COMPONENTS_BOTTOM", 9),
+    ("NOTES", 7),
+];
+
+/// Replace the characters which may not appear in a DXF symbol name.
+fn symbol_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "<>/\\\":;?*|=`".contains(c) { '_' } else { c })
+        .collect()
+}
+
+/// Writes DXF group codes and values.
+struct Dxf {
+    output: String,
+}
+
+impl Dxf {
+    fn group(&mut self, code: u16, value: impl std::fmt::Display) {
+        self.output.push_str(&format!("{}\n{}\n", code, value));
+    }
+
+    fn entity(&mut self, kind: &str, layer: &str) {
+        self.group(0, kind);
+        self.group(8, layer);
+    }
+
+    /// Write every loop of an IDF outline, as a closed POLYLINE with bulges for arcs or a
+    /// CIRCLE for a circular loop.
+    fn outline(&mut self, layer: &str, points: &[Point]) {
+        for points in split_loops(points) {
+            let Some(first) = points.first() else {
+                continue;
+            };
+            if let Some(end) = points.get(1).filter(|p| is_full_circle(p.angle)) {
+                let radius = ((end.x - first.x).powi(2) + (end.y - first.y).powi(2)).sqrt();
+                self.circle(layer, first.x, first.y, radius);
+                continue;
+            }
+            // The last point of a loop repeats the first, so the polyline is closed instead.
+            let closed = points.len() > 1
+                && (first.x, first.y) == (points[points.len() - 1].x, points[points.len() - 1].y);
+            let vertices = if closed {
+                points.len() - 1
+            } else {
+                points.len()
+            };
+            self.entity("POLYLINE", layer);
+            self.group(66, 1);
+            self.group(10, 0.0);
+            self.group(20, 0.0);
+            self.group(30, 0.0);
+            self.group(70, if closed { 1 } else { 0 });
+            for i in 0..vertices {
+                // The bulge of a vertex describes the segment which starts at it, while IDF
+                // stores the angle on the point which ends the segment.
+                let angle = points.get(i + 1).map_or(0.0, |p| p.angle);
+                self.entity("VERTEX", layer);
+                self.group(10, points[i].x);
+                self.group(20, points[i].y);
+                self.group(30, 0.0);
+                if angle.abs() > 1e-6 {
+                    self.group(42, (angle.to_radians() / 4.0).tan());
+                }
+            }
+            self.entity("SEQEND", layer);
+        }
+    }
+
+    fn circle(&mut self, layer: &str, x: f32, y: f32, radius: f32) {
+        self.entity("CIRCLE", layer);
+        self.group(10, x);
+        self.group(20, y);
+        self.group(30, 0.0);
+        self.group(40, radius);
+    }
+
+    fn text(&mut self, layer: &str, x: f32, y: f32, height: f32, text: &str) {
+        self.entity("TEXT", layer);
+        self.group(10, x);
+        self.group(20, y);
+        self.group(30, 0.0);
+        self.group(40, height);
+        self.group(1, text);
+        self.group(7, "STANDARD");
+    }
+
+    fn table(&mut self, name: &str, entries: usize) {
+        self.group(0, "TABLE");
+        self.group(2, name);
+        self.group(70, entries);
+    }
+
+    fn table_record(&mut self, kind: &str, name: &str) {
+        self.group(0, kind);
+        self.group(2, name);
+        self.group(70, 0);
+    }
+}
+
+/// Write a board or panel to the text of an AutoCAD R12 (AC1009) DXF file.
+///
+/// R12 is the most widely read DXF version, and needs no handles, object dictionaries or
+/// layouts. The file has a HEADER, TABLES with the CONTINUOUS line type, every layer and the
+/// STANDARD text style, BLOCKS and ENTITIES.
+///
+/// All geometry is converted to millimetres. The board outline and cutouts, other outlines,
+/// routing and placement outlines, keepouts and placement group areas are written on a layer
+/// named after their IDF section, as closed POLYLINEs with bulges for arcs, or as CIRCLEs for
+/// circular loops. Drilled holes are CIRCLEs on DRILLED_HOLES and notes are TEXT on NOTES.
+///
+/// When a library is given, the outline of each placed component is written once as a block
+/// named after its geometry and part number, and inserted at every placement on
+/// COMPONENTS_TOP or COMPONENTS_BOTTOM. Bottom side placements are mirrored about the block's y
+/// axis before rotating, as in IDF.
+///
+/// # Example
+/// ```
+/// use idf_parser::dxf::write_dxf;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let dxf = write_dxf(&board, Some(&library));
+/// assert!(dxf.contains("AC1009"));
+/// assert!(dxf.ends_with("EOF\n"));
+/// ```
+pub fn write_dxf(board: &BoardPanel, library: Option<&Library>) -> String {
+    let board = transform_board(board, &Transform::default(), "MM");
+    let index = library.map(|l| l.index());
+
+    // The component of each placement, and every distinct component by block name.
+    let mut blocks: BTreeMap<String, Component> = BTreeMap::new();
+    let mut inserts = Vec::new();
+    for placement in board.component_placements.iter() {
+        let Some(component) = index.as_ref().and_then(|i| i.for_placement(placement)) else {
+            continue;
+        };
+        let name = symbol_name(&format!(
+            "{}_{}",
+            component.geometry_name(),
+            component.part_number()
+        ));
+        blocks.insert(name.clone(), component);
+        inserts.push((name, placement));
+    }
+
+    let mut dxf = Dxf {
+        output: String::new(),
+    };
+
+    dxf.group(0, "SECTION");
+    dxf.group(2, "HEADER");
+    dxf.group(9, "$ACADVER");
+    dxf.group(1, "AC1009");
+    dxf.group(0, "ENDSEC");
+
+    dxf.group(0, "SECTION");
+    dxf.group(2, "TABLES");
+    dxf.table("LTYPE", 1);
+    dxf.table_record("LTYPE", "CONTINUOUS");
+    dxf.group(3, "Solid line");
+    dxf.group(72, 65);
+    dxf.group(73, 0);
+    dxf.group(40, 0.0);
+    dxf.group(0, "ENDTAB");
+    dxf.table("LAYER", LAYERS.len() + 1);
+    for (name, colour) in std::iter::once(("0", 7)).chain(LAYERS) {
+        dxf.table_record("LAYER", name);
+        dxf.group(62, colour);
+        dxf.group(6, "CONTINUOUS");
+    }
+    dxf.group(0, "ENDTAB");
+    dxf.table("STYLE", 1);
+    dxf.table_record("STYLE", "STANDARD");
+    dxf.group(40, 0.0);
+    dxf.group(41, 1.0);
+    dxf.group(50, 0.0);
+    dxf.group(71, 0);
+    dxf.group(42, 2.5);
+    dxf.group(3, "txt");
+    dxf.group(4, "");
+    dxf.group(0, "ENDTAB");
+    dxf.group(0, "ENDSEC");
+
+    dxf.group(0, "SECTION");
+    dxf.group(2, "BLOCKS");
+    for (name, component) in blocks.iter() {
+        let scale = mm_per_unit(component.units());
+        let outline: Vec<Point> = component
+            .outline()
+            .iter()
+            .map(|p| Point {
+                x: p.x * scale,
+                y: p.y * scale,
+                ..p.clone()
+            })
+            .collect();
+        dxf.entity("BLOCK", "0");
+        dxf.group(2, name);
+        dxf.group(70, 0);
+        dxf.group(10, 0.0);
+        dxf.group(20, 0.0);
+        dxf.group(30, 0.0);
+        dxf.group(3, name);
+        dxf.outline("0", &outline);
+        dxf.entity("ENDBLK", "0");
+    }
+    dxf.group(0, "ENDSEC");
+
+    dxf.group(0, "SECTION");
+    dxf.group(2, "ENTITIES");
+    dxf.outline("BOARD_OUTLINE", &board.outline.outline);
+    for o in board.other_outlines.iter() {
+        dxf.outline("OTHER_OUTLINE", &o.outline);
+    }
+    for o in board.routing_outlines.iter() {
+        dxf.outline("ROUTE_OUTLINE", &o.outline);
+    }
+    for o in board.placement_outlines.iter() {
+        dxf.outline("PLACE_OUTLINE", &o.outline);
+    }
+    for k in board.routing_keepouts.iter() {
+        dxf.outline("ROUTE_KEEPOUT", &k.outline);
+    }
+    for k in board.via_keepouts.iter() {
+        dxf.outline("VIA_KEEPOUT", &k.outline);
+    }
+    for k in board.placement_keepouts.iter() {
+        dxf.outline("PLACE_KEEPOUT", &k.outline);
+    }
+    for a in board.placement_group_areas.iter() {
+        dxf.outline("PLACE_REGION", &a.outline);
+    }
+    for hole in board.drilled_holes.iter() {
+        dxf.circle("DRILLED_HOLES", hole.x, hole.y, hole.diameter / 2.0);
+    }
+    for note in board.notes.iter() {
+        dxf.text("NOTES", note.x, note.y, note.text_height, &note.text);
+    }
+    for (name, placement) in inserts {
+        let bottom = placement.board_side == "BOTTOM";
+        let layer = if bottom {
+            "COMPONENTS_BOTTOM"
+        } else {
+            "COMPONENTS_TOP"
+        };
+        dxf.entity("INSERT", layer);
+        dxf.group(2, name);
+        dxf.group(10, placement.x);
+        dxf.group(20, placement.y);
+        dxf.group(30, 0.0);
+        if bottom {
+            dxf.group(41, -1.0);
+        }
+        dxf.group(50, placement.rotation_angle);
+    }
+    dxf.group(0, "ENDSEC");
+    dxf.group(0, "EOF");
+    dxf.output
+}
+
+/// A line or arc segment read from a DXF file, sweeping `angle` degrees counter-clockwise from
+/// `start` to `end`, or 0 for a line.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: Vertex,
+    end: Vertex,
+    angle: f32,
+}
+
+impl Segment {
+    fn reversed(&self) -> Segment {
+        Segment {
+            start: self.end,
+            end: self.start,
+            angle: -self.angle,
+        }
+    }
+}
+
+/// The group codes and values of one DXF entity.
+struct Entity<'a> {
+    kind: &'a str,
+    groups: Vec<(u16, &'a str)>,
+}
+
+impl Entity<'_> {
+    fn value(&self, code: u16) -> Option<&str> {
+        self.groups.iter().find(|g| g.0 == code).map(|g| g.1)
+    }
+
+    fn number(&self, code: u16) -> Result<f32, String> {
+        let value = self.value(code).unwrap_or("0");
+        value.parse().map_err(|_| {
+            format!(
+                "Invalid value {} for group {} of a {}.",
+                value, code, self.kind
+            )
+        })
+    }
+}
+
+/// The entities of the ENTITIES section of a DXF file.
+fn dxf_entities(input: &str) -> Result<Vec<Entity<'_>>, String> {
+    let lines: Vec<&str> = input.lines().map(|l| l.trim()).collect();
+    let mut groups = Vec::new();
+    for pair in lines.chunks(2) {
+        let [code, value] = pair else {
+            return Err("DXF file ends with a group code without a value.".to_string());
+        };
+        let code = code
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid DXF group code {}.", code))?;
+        groups.push((code, *value));
+    }
+
+    let mut entities = Vec::new();
+    let mut in_entities = false;
+    let mut i = 0;
+    while i < groups.len() {
+        let (code, value) = groups[i];
+        i += 1;
+        if code != 0 {
+            continue;
+        }
+        match value {
+            "SECTION" => in_entities = groups.get(i) == Some(&(2, "ENTITIES")),
+            "ENDSEC" => in_entities = false,
+            kind if in_entities => {
+                let end = groups[i..]
+                    .iter()
+                    .position(|g| g.0 == 0)
+                    .map_or(groups.len(), |p| i + p);
+                entities.push(Entity {
+                    kind,
+                    groups: groups[i..end].to_vec(),
+                });
+                i = end;
+            }
+            _ => {}
+        }
+    }
+    Ok(entities)
+}
+
+/// Whether the flags of a POLYLINE or LWPOLYLINE mark it as closed.
+fn is_closed(entity: &Entity) -> bool {
+    entity
+        .value(70)
+        .and_then(|f| f.parse::<u16>().ok())
+        .unwrap_or(0)
+        & 1
+        == 1
+}
+
+/// The segments of a polyline from its vertices, each with the bulge of the segment starting at
+/// it, leaving out segments of zero length.
+fn polyline_segments(vertices: &[(Vertex, f32)], closed: bool) -> Vec<Segment> {
+    let count = if closed {
+        vertices.len()
+    } else {
+        vertices.len().saturating_sub(1)
+    };
+    (0..count)
+        .filter_map(|i| {
+            let ((start, bulge), (end, _)) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+            (start != end).then(|| Segment {
+                start,
+                end,
+                angle: 4.0 * bulge.atan().to_degrees(),
+            })
+        })
+        .collect()
+}
+
+/// Chain segments into closed loops of IDF points, each loop starting with a point at the start
+/// of its first segment and ending with a point repeating it.
+fn chain_segments(mut segments: Vec<Segment>, tolerance: f32) -> Result<Vec<Vec<Point>>, String> {
+    let close =
+        |a: Vertex, b: Vertex| (a.0 - b.0).abs() <= tolerance && (a.1 - b.1).abs() <= tolerance;
+    let point = |(x, y): Vertex, angle: f32| Point {
+        loop_label: 0,
+        x,
+        y,
+        angle,
+    };
+    let mut loops = Vec::new();
+    while !segments.is_empty() {
+        let first = segments.remove(0);
+        let mut points = vec![point(first.start, 0.0), point(first.end, first.angle)];
+        let mut end = first.end;
+        while !close(end, first.start) {
+            let next = segments
+                .iter()
+                .position(|s| close(s.start, end) || close(s.end, end));
+            let Some(next) = next else {
+                return Err(format!(
+                    "Open chain of segments ending at ({}, {}).",
+                    end.0, end.1
+                ));
+            };
+            let segment = segments.remove(next);
+            let segment = if close(segment.start, end) {
+                segment
+            } else {
+                segment.reversed()
+            };
+            points.push(point(segment.end, segment.angle));
+            end = segment.end;
+        }
+        // Close the loop exactly on its first point.
+        let last = points.len() - 1;
+        (points[last].x, points[last].y) = first.start;
+        loops.push(points);
+    }
+    Ok(loops)
+}
+
+/// Read the closed loops drawn on a layer of a DXF file as IDF outline points.
+///
+/// LINE, ARC, CIRCLE, POLYLINE and LWPOLYLINE entities of the ENTITIES section on the layer are
+/// read and chained end to end into loops, joining ends within `tolerance` of each other. Lines
+/// and arcs may be drawn in either direction. Each loop is returned with a loop label of 0,
+/// starting and ending on the same point, and each circle as a circle centre followed by a point
+/// on the circle with an angle of 360. Coordinates are in the units of the drawing.
+pub fn read_dxf_loops(input: &str, layer: &str, tolerance: f32) -> Result<Vec<Vec<Point>>, String> {
+    let mut segments = Vec::new();
+    let mut circles = Vec::new();
+    // The vertices of a POLYLINE on the layer, and whether it is closed, until its SEQEND.
+    let mut polyline: Option<(Vec<(Vertex, f32)>, bool)> = None;
+    for entity in dxf_entities(input)? {
+        // The VERTEX and SEQEND entities of a POLYLINE belong to it, whatever their layer.
+        if let Some((vertices, closed)) = polyline.as_mut() {
+            match entity.kind {
+                "VERTEX" => {
+                    let position = (entity.number(10)?, entity.number(20)?);
+                    vertices.push((position, entity.number(42)?));
+                    continue;
+                }
+                "SEQEND" => {
+                    segments.extend(polyline_segments(vertices, *closed));
+                    polyline = None;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if entity.value(8) != Some(layer) {
+            continue;
+        }
+        match entity.kind {
+            "LINE" => segments.push(Segment {
+                start: (entity.number(10)?, entity.number(20)?),
+                end: (entity.number(11)?, entity.number(21)?),
+                angle: 0.0,
+            }),
+            "ARC" => {
+                let (x, y, radius) = (entity.number(10)?, entity.number(20)?, entity.number(40)?);
+                let (start, end) = (entity.number(50)?, entity.number(51)?);
+                let on_arc = |angle: f32| {
+                    let (sin, cos) = angle.to_radians().sin_cos();
+                    (x + radius * cos, y + radius * sin)
+                };
+                segments.push(Segment {
+                    start: on_arc(start),
+                    end: on_arc(end),
+                    angle: (end - start).rem_euclid(360.0),
+                });
+            }
+            "CIRCLE" => {
+                let (x, y, radius) = (entity.number(10)?, entity.number(20)?, entity.number(40)?);
+                circles.push(vec![
+                    Point {
+                        loop_label: 0,
+                        x,
+                        y,
+                        angle: 0.0,
+                    },
+                    Point {
+                        loop_label: 0,
+                        x: x + radius,
+                        y,
+                        angle: 360.0,
+                    },
+                ]);
+            }
+            "LWPOLYLINE" => {
+                // Each vertex starts with an x coordinate, followed by its y and optional bulge.
+                let mut vertices: Vec<(Vertex, f32)> = Vec::new();
+                for &(code, value) in entity.groups.iter() {
+                    let number = || {
+                        value.parse::<f32>().map_err(|_| {
+                            format!(
+                                "Invalid value {} for group {} of a LWPOLYLINE.",
+                                value, code
+                            )
+                        })
+                    };
+                    match (code, vertices.last_mut()) {
+                        (10, _) => vertices.push(((number()?, 0.0), 0.0)),
+                        (20, Some(vertex)) => vertex.0.1 = number()?,
+                        (42, Some(vertex)) => vertex.1 = number()?,
+                        _ => {}
+                    }
+                }
+                segments.extend(polyline_segments(&vertices, is_closed(&entity)));
+            }
+            "POLYLINE" => polyline = Some((Vec::new(), is_closed(&entity))),
+            _ => {}
+        }
+    }
+    let mut loops = chain_segments(segments, tolerance)?;
+    loops.extend(circles);
+    Ok(loops)
+}
+
+/// Read a board or panel outline drawn on a layer of a DXF file.
+///
+/// The loop enclosing the largest area becomes the outline, with loop label 0, and every other
+/// loop a cutout with labels counting up from 1. The outline is owned by MCAD.
+///
+/// # Example
+/// ```
+/// use idf_parser::dxf::{import_board_outline, write_dxf};
+/// use idf_parser::parse_board_file;
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let dxf = write_dxf(&board, None);
+/// let outline = import_board_outline(&dxf, "BOARD_OUTLINE", 1.6, 0.001).unwrap();
+/// assert_eq!(outline.outline.len(), board.outline.outline.len());
+/// ```
+pub fn import_board_outline(
+    input: &str,
+    layer: &str,
+    thickness: f32,
+    tolerance: f32,
+) -> Result<BoardPanelOutline, String> {
+    let mut loops = read_dxf_loops(input, layer, tolerance)?;
+    let outer = (0..loops.len())
+        .max_by(|&a, &b| {
+            let area = |i: usize| Polygon::from_outline(&loops[i], tolerance).area();
+            area(a).total_cmp(&area(b))
+        })
+        .ok_or_else(|| format!("No closed loops were found on layer {}.", layer))?;
+    let outer = loops.remove(outer);
+    loops.insert(0, outer);
+
+    let mut outline = Vec::new();
+    for (label, points) in loops.into_iter().enumerate() {
+        outline.extend(points.into_iter().map(|p| Point {
+            loop_label: label as u32,
+            ..p
+        }));
+    }
+    Ok(BoardPanelOutline {
+        owner: "MCAD".to_string(),
+        thickness,
+        outline,
+    })
+}
+
+/// Read the loops drawn on a layer of a DXF file as MCAD owned placement keepouts, one per loop.
+pub fn import_placement_keepouts(
+    input: &str,
+    layer: &str,
+    board_side: &str,
+    keepout_height: f32,
+    tolerance: f32,
+) -> Result<Vec<PlacementKeepout>, String> {
+    Ok(read_dxf_loops(input, layer, tolerance)?
+        .into_iter()
+        .map(|outline| PlacementKeepout {
+            owner: "MCAD".to_string(),
+            board_side: board_side.to_string(),
+            keepout_height,
+            outline,
+        })
+        .collect())
+}
+
+/// Read the loops drawn on a layer of a DXF file as MCAD owned routing keepouts, one per loop.
+pub fn import_routing_keepouts(
+    input: &str,
+    layer: &str,
+    routing_layers: &str,
+    tolerance: f32,
+) -> Result<Vec<RoutingKeepout>, String> {
+    Ok(read_dxf_loops(input, layer, tolerance)?
+        .into_iter()
+        .map(|outline| RoutingKeepout {
+            owner: "MCAD".to_string(),
+            routing_layers: routing_layers.to_string(),
+            outline,
+        })
+        .collect())
+}
+
+/// Read the loops drawn on a layer of a DXF file as MCAD owned via keepouts, one per loop.
+pub fn import_via_keepouts(
+    input: &str,
+    layer: &str,
+    tolerance: f32,
+) -> Result<Vec<ViaKeepout>, String> {
+    Ok(read_dxf_loops(input, layer, tolerance)?
+        .into_iter()
+        .map(|outline| ViaKeepout {
+            owner: "MCAD".to_string(),
+            outline,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    /// The group code and value pairs of a DXF file.
+    fn groups(dxf: &str) -> Vec<(u16, &str)> {
+        let lines: Vec<&str> = dxf.lines().collect();
+        assert_eq!(lines.len() % 2, 0);
+        lines
+            .chunks(2)
+            .map(|pair| (pair[0].parse().unwrap(), pair[1]))
+            .collect()
+    }
+
+    fn count(groups: &[(u16, &str)], kind: &str) -> usize {
+        groups.iter().filter(|&&g| g == (0, kind)).count()
+    }
+
+    #[test]
+    fn test_write_board() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let dxf = write_dxf(&board, None);
+        let groups = groups(&dxf);
+
+        // The outline, route outline, two placement outlines and one placement keepout are
+        // polylines, while the circular cutout and keepouts are circles alongside the holes.
+        assert_eq!(count(&groups, "POLYLINE"), 5);
+        assert_eq!(count(&groups, "SEQEND"), 5);
+        assert_eq!(count(&groups, "CIRCLE"), board.drilled_holes.len() + 3);
+        assert_eq!(count(&groups, "TEXT"), 3);
+        assert_eq!(count(&groups, "INSERT"), 0);
+
+        // The semicircular notches of the outline are written as bulges of -1.
+        let entities = groups.iter().position(|&g| g == (2, "ENTITIES")).unwrap();
+        let bulges: Vec<&str> = groups[entities..]
+            .iter()
+            .filter(|g| g.0 == 42)
+            .map(|g| g.1)
+            .collect();
+        assert_eq!(&bulges[..2], ["-1", "-1"]);
+
+        // Coordinates are converted from thou to millimetres.
+        let hole = groups
+            .iter()
+            .position(|&g| g == (8, "DRILLED_HOLES"))
+            .unwrap();
+        assert_eq!(groups[hole + 1], (10, "45.72"));
+    }
+
+    #[test]
+    fn test_write_r12_structure() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let dxf = write_dxf(&board, Some(&library));
+        let groups = groups(&dxf);
+
+        let sections: Vec<&str> = groups
+            .windows(2)
+            .filter(|w| w[0] == (0, "SECTION"))
+            .map(|w| w[1].1)
+            .collect();
+        assert_eq!(sections, ["HEADER", "TABLES", "BLOCKS", "ENTITIES"]);
+        assert_eq!(count(&groups, "ENDSEC"), 4);
+        assert_eq!(groups.last(), Some(&(0, "EOF")));
+        assert!(groups.contains(&(1, "AC1009")));
+
+        let tables: Vec<&str> = groups
+            .windows(2)
+            .filter(|w| w[0] == (0, "TABLE"))
+            .map(|w| w[1].1)
+            .collect();
+        assert_eq!(tables, ["LTYPE", "LAYER", "STYLE"]);
+        assert_eq!(count(&groups, "ENDTAB"), 3);
+
+        // R12 has no handles or subclass markers.
+        assert!(!groups.iter().any(|g| g.0 == 5 || g.0 == 100));
+
+        // Every layer, block and text style used is defined.
+        let defined = |kind: &str| -> Vec<&str> {
+            groups
+                .windows(2)
+                .filter(|w| w[0] == (0, kind))
+                .map(|w| w[1].1)
+                .collect()
+        };
+        let layers = defined("LAYER");
+        assert!(
+            groups
+                .iter()
+                .filter(|g| g.0 == 8)
+                .all(|g| layers.contains(&g.1))
+        );
+        let blocks: Vec<&str> = groups
+            .windows(3)
+            .filter(|w| w[0] == (0, "BLOCK"))
+            .map(|w| w[2].1)
+            .collect();
+        assert_eq!(count(&groups, "ENDBLK"), blocks.len());
+        let mut inserts = groups.windows(3).filter(|w| w[0] == (0, "INSERT"));
+        assert!(inserts.all(|w| blocks.contains(&w[2].1)));
+        assert_eq!(defined("STYLE"), ["STANDARD"]);
+        assert_eq!(defined("LTYPE"), ["CONTINUOUS"]);
+    }
+
+    #[test]
+    fn test_write_components() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let dxf = write_dxf(&board, Some(&library));
+        let groups = groups(&dxf);
+
+        assert_eq!(count(&groups, "BLOCK"), 5);
+        assert_eq!(count(&groups, "INSERT"), board.component_placements.len());
+        let bottom = groups
+            .iter()
+            .filter(|&&g| g == (8, "COMPONENTS_BOTTOM"))
+            .count();
+        assert_eq!(bottom, 3);
+        assert_eq!(groups.iter().filter(|&&g| g == (41, "-1")).count(), 3);
+        assert!(groups.contains(&(2, "cs13_a_pn-cap")));
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let metric = transform_board(&board, &Transform::default(), "MM");
+        let dxf = write_dxf(&board, None);
+
+        let outline = import_board_outline(&dxf, "BOARD_OUTLINE", 1.5748, 0.001).unwrap();
+        assert_eq!(outline.outline.len(), metric.outline.outline.len());
+        for (a, b) in outline.outline.iter().zip(metric.outline.outline.iter()) {
+            assert_eq!(a.loop_label, b.loop_label);
+            assert!((a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4);
+            assert!((a.angle - b.angle).abs() < 1e-3);
+        }
+
+        let keepouts = import_placement_keepouts(&dxf, "PLACE_KEEPOUT", "TOP", 5.0, 0.001).unwrap();
+        assert_eq!(keepouts.len(), 2);
+        assert!(
+            import_via_keepouts(&dxf, "VIA_KEEPOUT", 0.001)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_chain_lines_and_arcs() {
+        // A 10 x 10 square with a rounded corner drawn as unordered lines and an arc, one line
+        // drawn backwards, and a separate closed polyline with a bulge.
+        let dxf = "0\nSECTION\n2\nENTITIES\n\
+            0\nLINE\n8\nEDGE\n10\n0\n20\n0\n11\n10\n21\n0\n\
+            0\nLINE\n8\nEDGE\n10\n0\n20\n10\n11\n0\n21\n0\n\
+            0\nARC\n8\nEDGE\n10\n8\n20\n8\n40\n2\n50\n0\n51\n90\n\
+            0\nLINE\n8\nEDGE\n10\n10\n20\n0\n11\n10\n21\n8\n\
+            0\nLINE\n8\nEDGE\n10\n8\n20\n10\n11\n0\n21\n10\n\
+            0\nLWPOLYLINE\n8\nEDGE\n90\n2\n70\n1\n10\n4\n20\n5\n42\n1\n10\n6\n20\n5\n42\n1\n\
+            0\nLINE\n8\nOTHER\n10\n0\n20\n0\n11\n5\n21\n5\n\
+            0\nENDSEC\n0\nEOF\n";
+        let outline = import_board_outline(dxf, "EDGE", 1.6, 0.001).unwrap();
+        let loops = split_loops(&outline.outline);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].len(), 6);
+        assert_eq!(loops[0].iter().filter(|p| p.angle == 90.0).count(), 1);
+        assert_eq!((loops[0][5].x, loops[0][5].y), (0.0, 0.0));
+        assert_eq!(loops[1].len(), 3);
+        assert!(
+            loops[1]
+                .iter()
+                .skip(1)
+                .all(|p| (p.angle - 180.0).abs() < 1e-3)
+        );
+        let area = Polygon::from_outline(&outline.outline, 0.001).area();
+        // The rounded corner removes 4 - pi and the unit circle cutout removes pi.
+        assert!((area - 96.0).abs() < 0.01);
+
+        // An open chain is an error.
+        assert!(read_dxf_loops(dxf, "OTHER", 0.001).is_err());
+    }
+}
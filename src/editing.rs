@@ -0,0 +1,564 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::drilled_holes::Hole;
+use crate::geometry::Transform;
+use crate::library::{Library, LibraryIndex};
+use crate::notes::Note;
+use crate::outlines::{PlacementKeepout, RoutingKeepout, ViaKeepout};
+
+/// The system making a change to a board, either the electrical or the mechanical design system.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Domain {
+    Ecad,
+    Mcad,
+}
+
+impl Domain {
+    /// The owner and placement status string of the domain, "ECAD" or "MCAD".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Domain::Ecad => "ECAD",
+            Domain::Mcad => "MCAD",
+        }
+    }
+
+    /// The other domain.
+    pub fn other(&self) -> Domain {
+        match self {
+            Domain::Ecad => Domain::Mcad,
+            Domain::Mcad => Domain::Ecad,
+        }
+    }
+
+    /// Whether the domain may modify an entity with the given owner, i.e. the owner is the domain
+    /// itself or UNOWNED.
+    pub fn can_modify(&self, owner: &str) -> bool {
+        owner != self.other().as_str()
+    }
+
+    /// Whether the domain may modify a placement with the given placement status. `PLACED` and
+    /// `UNPLACED` placements may be modified by either domain, `ECAD` and `MCAD` placements only
+    /// by that domain.
+    pub fn can_modify_placement(&self, placement_status: &str) -> bool {
+        self.can_modify(placement_status)
+    }
+}
+
+/// Edits a board on behalf of one domain, checking the board stays consistent and that the
+/// domain only changes entities it owns.
+///
+/// # Example
+/// ```
+/// use idf_parser::editing::{BoardEditor, Domain};
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+///
+/// let mut editor = BoardEditor::new(&mut board, Domain::Ecad).with_library(&library);
+/// editor.move_placement("C2", 3100.0, 3500.0).unwrap();
+/// editor.rotate_placement("C2", 90.0).unwrap();
+/// assert!(editor.move_placement("missing", 0.0, 0.0).is_err());
+///
+/// let c2 = &board.component_placements[1];
+/// assert_eq!((c2.x, c2.rotation_angle), (3100.0, 90.0));
+/// ```
+#[derive(Debug)]
+pub struct BoardEditor<'a> {
+    board: &'a mut BoardPanel,
+    domain: Domain,
+    library: Option<LibraryIndex<'a>>,
+}
+
+impl<'a> BoardEditor<'a> {
+    pub fn new(board: &'a mut BoardPanel, domain: Domain) -> Self {
+        BoardEditor {
+            board,
+            domain,
+            library: None,
+        }
+    }
+
+    /// Check that the packages of added placements are defined in the library.
+    pub fn with_library(mut self, library: &'a Library) -> Self {
+        self.library = Some(library.index());
+        self
+    }
+
+    pub fn board(&self) -> &BoardPanel {
+        self.board
+    }
+
+    fn check_owner(&self, kind: &str, owner: &str) -> Result<(), String> {
+        if self.domain.can_modify(owner) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} cannot modify {} owned by {}.",
+                self.domain.as_str(),
+                kind,
+                owner
+            ))
+        }
+    }
+
+    fn placement_index(&self, refdes: &str) -> Result<usize, String> {
+        let index = self
+            .board
+            .component_placements
+            .iter()
+            .position(|p| p.reference_designator == refdes)
+            .ok_or_else(|| format!("Placement {} not found.", refdes))?;
+        let status = &self.board.component_placements[index].placement_status;
+        if !self.domain.can_modify_placement(status) {
+            return Err(format!(
+                "{} cannot modify placement {} with status {}.",
+                self.domain.as_str(),
+                refdes,
+                status
+            ));
+        }
+        Ok(index)
+    }
+
+    fn placement_mut(&mut self, refdes: &str) -> Result<&mut ComponentPlacement, String> {
+        let index = self.placement_index(refdes)?;
+        Ok(&mut self.board.component_placements[index])
+    }
+
+    /// The indices of the PIN holes of a placement, checking the domain may `action` them.
+    ///
+    /// Another placement may share the reference designator, e.g. NOREFDES, in which case its
+    /// holes cannot be told apart and none are returned.
+    fn pin_holes(&self, refdes: &str, action: &str) -> Result<Vec<usize>, String> {
+        let shared = self
+            .board
+            .component_placements
+            .iter()
+            .filter(|p| p.reference_designator == refdes)
+            .count()
+            > 1;
+        if shared {
+            return Ok(Vec::new());
+        }
+        let pins: Vec<usize> = (0..self.board.drilled_holes.len())
+            .filter(|&i| {
+                let hole = &self.board.drilled_holes[i];
+                hole.hole_type == "PIN" && hole.associated_part == refdes
+            })
+            .collect();
+        if let Some(hole) = pins
+            .iter()
+            .map(|&i| &self.board.drilled_holes[i])
+            .find(|h| !self.domain.can_modify(&h.owner))
+        {
+            return Err(format!(
+                "{} cannot {} placement {} as its PIN holes are owned by {}.",
+                self.domain.as_str(),
+                action,
+                refdes,
+                hole.owner
+            ));
+        }
+        Ok(pins)
+    }
+
+    /// Change a placement, carrying its PIN holes along so that they keep their position
+    /// relative to it.
+    fn edit_placement(
+        &mut self,
+        refdes: &str,
+        action: &str,
+        edit: impl FnOnce(&mut ComponentPlacement) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let index = self.placement_index(refdes)?;
+        let pins = self.pin_holes(refdes, action)?;
+        let mut placement = self.board.component_placements[index].clone();
+        let before = Transform::from_placement(&placement);
+        edit(&mut placement)?;
+        let after = Transform::from_placement(&placement);
+        for i in pins {
+            let hole = &mut self.board.drilled_holes[i];
+            let (x, y) = before.invert(hole.x, hole.y);
+            (hole.x, hole.y) = after.apply(x, y);
+        }
+        self.board.component_placements[index] = placement;
+        Ok(())
+    }
+
+    /// Move a placement to the given position, along with its PIN holes.
+    pub fn move_placement(&mut self, refdes: &str, x: f32, y: f32) -> Result<(), String> {
+        self.edit_placement(refdes, "move", |placement| {
+            placement.x = x;
+            placement.y = y;
+            Ok(())
+        })
+    }
+
+    /// Set the rotation of a placement in degrees, normalised to [0, 360), turning its PIN
+    /// holes with it.
+    pub fn rotate_placement(&mut self, refdes: &str, rotation_angle: f32) -> Result<(), String> {
+        self.edit_placement(refdes, "rotate", |placement| {
+            placement.rotation_angle = rotation_angle.rem_euclid(360.0);
+            Ok(())
+        })
+    }
+
+    /// Move a placement to the other side of the board, mirroring it and its PIN holes about
+    /// its own y axis.
+    ///
+    /// BOTTOM placements are mirrored before being rotated, so the rotation is negated to keep
+    /// the outline in the same orientation on the board.
+    pub fn flip_placement(&mut self, refdes: &str) -> Result<(), String> {
+        self.edit_placement(refdes, "flip", |placement| {
+            placement.board_side = match placement.board_side.as_str() {
+                "TOP" => "BOTTOM".to_string(),
+                "BOTTOM" => "TOP".to_string(),
+                side => return Err(format!("Placement {} has unknown side {}.", refdes, side)),
+            };
+            placement.rotation_angle = (-placement.rotation_angle).rem_euclid(360.0);
+            Ok(())
+        })
+    }
+
+    /// Change the placement status, which must be PLACED, UNPLACED, ECAD or MCAD.
+    ///
+    /// A domain may not hand a placement to the other domain, as it would then be unable to
+    /// undo the change.
+    pub fn set_placement_status(&mut self, refdes: &str, status: &str) -> Result<(), String> {
+        if !["PLACED", "UNPLACED", "ECAD", "MCAD"].contains(&status) {
+            return Err(format!("Unknown placement status {}.", status));
+        }
+        if !self.domain.can_modify_placement(status) {
+            return Err(format!(
+                "{} cannot set the status of {} to {}.",
+                self.domain.as_str(),
+                refdes,
+                status
+            ));
+        }
+        self.placement_mut(refdes)?.placement_status = status.to_string();
+        Ok(())
+    }
+
+    /// Add a placement. The reference designator must not already be used, except for
+    /// `NOREFDES` and panel `BOARD` placements, and the package must be in the library if one
+    /// was provided.
+    pub fn add_placement(&mut self, placement: ComponentPlacement) -> Result<(), String> {
+        let refdes = &placement.reference_designator;
+        let is_board = refdes == "BOARD";
+        if refdes != "NOREFDES"
+            && !is_board
+            && self
+                .board
+                .component_placements
+                .iter()
+                .any(|p| &p.reference_designator == refdes)
+        {
+            return Err(format!("Placement {} already exists.", refdes));
+        }
+        if let Some(library) = &self.library
+            && !is_board
+            && library.get(&placement.package_name).is_none()
+        {
+            return Err(format!(
+                "Package {} of {} is not in the library.",
+                placement.package_name, refdes
+            ));
+        }
+        if !self
+            .domain
+            .can_modify_placement(&placement.placement_status)
+        {
+            return Err(format!(
+                "{} cannot add placement {} with status {}.",
+                self.domain.as_str(),
+                refdes,
+                placement.placement_status
+            ));
+        }
+        self.board.component_placements.push(placement);
+        Ok(())
+    }
+
+    /// Remove a placement along with its PIN holes, returning the placement.
+    ///
+    /// A PIN hole must belong to a placement, so the placement may only be removed if the domain
+    /// may also remove every PIN hole associated with it.
+    pub fn remove_placement(&mut self, refdes: &str) -> Result<ComponentPlacement, String> {
+        let index = self.placement_index(refdes)?;
+        let pins = self.pin_holes(refdes, "remove")?;
+        for i in pins.into_iter().rev() {
+            self.board.drilled_holes.remove(i);
+        }
+        Ok(self.board.component_placements.remove(index))
+    }
+
+    /// Add a drilled hole. A PIN hole must belong to a placement on the board.
+    pub fn add_hole(&mut self, hole: Hole) -> Result<(), String> {
+        self.check_owner("a hole", &hole.owner)?;
+        if hole.hole_type == "PIN"
+            && !self
+                .board
+                .component_placements
+                .iter()
+                .any(|p| p.reference_designator == hole.associated_part)
+        {
+            return Err(format!(
+                "PIN hole belongs to {} which is not placed on the board.",
+                hole.associated_part
+            ));
+        }
+        self.board.drilled_holes.push(hole);
+        Ok(())
+    }
+
+    /// Remove the drilled hole at the given index, returning it.
+    pub fn remove_hole(&mut self, index: usize) -> Result<Hole, String> {
+        let hole = self
+            .board
+            .drilled_holes
+            .get(index)
+            .ok_or_else(|| format!("Hole {} not found.", index))?;
+        self.check_owner("a hole", &hole.owner)?;
+        Ok(self.board.drilled_holes.remove(index))
+    }
+
+    pub fn add_routing_keepout(&mut self, keepout: RoutingKeepout) -> Result<(), String> {
+        self.check_owner("a routing keepout", &keepout.owner)?;
+        self.board.routing_keepouts.push(keepout);
+        Ok(())
+    }
+
+    pub fn remove_routing_keepout(&mut self, index: usize) -> Result<RoutingKeepout, String> {
+        let keepout = self
+            .board
+            .routing_keepouts
+            .get(index)
+            .ok_or_else(|| format!("Routing keepout {} not found.", index))?;
+        self.check_owner("a routing keepout", &keepout.owner)?;
+        Ok(self.board.routing_keepouts.remove(index))
+    }
+
+    pub fn add_via_keepout(&mut self, keepout: ViaKeepout) -> Result<(), String> {
+        self.check_owner("a via keepout", &keepout.owner)?;
+        self.board.via_keepouts.push(keepout);
+        Ok(())
+    }
+
+    pub fn remove_via_keepout(&mut self, index: usize) -> Result<ViaKeepout, String> {
+        let keepout = self
+            .board
+            .via_keepouts
+            .get(index)
+            .ok_or_else(|| format!("Via keepout {} not found.", index))?;
+        self.check_owner("a via keepout", &keepout.owner)?;
+        Ok(self.board.via_keepouts.remove(index))
+    }
+
+    pub fn add_placement_keepout(&mut self, keepout: PlacementKeepout) -> Result<(), String> {
+        self.check_owner("a placement keepout", &keepout.owner)?;
+        self.board.placement_keepouts.push(keepout);
+        Ok(())
+    }
+
+    pub fn remove_placement_keepout(&mut self, index: usize) -> Result<PlacementKeepout, String> {
+        let keepout = self
+            .board
+            .placement_keepouts
+            .get(index)
+            .ok_or_else(|| format!("Placement keepout {} not found.", index))?;
+        self.check_owner("a placement keepout", &keepout.owner)?;
+        Ok(self.board.placement_keepouts.remove(index))
+    }
+
+    /// Add a note. Notes have no owner and may be edited by either domain.
+    pub fn add_note(&mut self, note: Note) {
+        self.board.notes.push(note);
+    }
+
+    /// Remove the note at the given index, returning it.
+    pub fn remove_note(&mut self, index: usize) -> Result<Note, String> {
+        if index >= self.board.notes.len() {
+            return Err(format!("Note {} not found.", index));
+        }
+        Ok(self.board.notes.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hole_checks::{HoleCheckConfig, HoleRule, HoleRuleSet};
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_domain_ownership() {
+        assert!(Domain::Ecad.can_modify("ECAD"));
+        assert!(Domain::Ecad.can_modify("UNOWNED"));
+        assert!(!Domain::Ecad.can_modify("MCAD"));
+        assert!(Domain::Mcad.can_modify_placement("PLACED"));
+        assert!(!Domain::Mcad.can_modify_placement("ECAD"));
+    }
+
+    #[test]
+    fn test_placement_edits() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let mut editor = BoardEditor::new(&mut board, Domain::Mcad).with_library(&library);
+
+        editor.rotate_placement("C1", -90.0).unwrap();
+        editor.flip_placement("C1").unwrap();
+        let c1 = &editor.board().component_placements[0];
+        assert_eq!(
+            (c1.board_side.as_str(), c1.rotation_angle),
+            ("BOTTOM", 90.0)
+        );
+
+        editor.set_placement_status("C1", "MCAD").unwrap();
+        assert!(editor.set_placement_status("C1", "ECAD").is_err());
+        assert!(editor.set_placement_status("C1", "GONE").is_err());
+
+        let mut new = editor.board().component_placements[1].clone();
+        assert!(editor.add_placement(new.clone()).is_err());
+        new.reference_designator = "C99".to_string();
+        editor.add_placement(new.clone()).unwrap();
+        new.reference_designator = "C100".to_string();
+        new.package_name = "not_in_library".to_string();
+        assert!(editor.add_placement(new).is_err());
+
+        assert_eq!(
+            editor.remove_placement("C99").unwrap().reference_designator,
+            "C99"
+        );
+        assert!(editor.remove_placement("C99").is_err());
+    }
+
+    #[test]
+    fn test_remove_placement_removes_pin_holes() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let pins = |board: &BoardPanel| {
+            board
+                .drilled_holes
+                .iter()
+                .filter(|h| h.hole_type == "PIN" && h.associated_part == "J1")
+                .count()
+        };
+        assert!(pins(&board) > 0);
+
+        // J1 is placed by MCAD but its holes are owned by ECAD, so MCAD may not remove it.
+        let mut editor = BoardEditor::new(&mut board, Domain::Mcad);
+        let error = editor.remove_placement("J1").unwrap_err();
+        assert!(error.contains("PIN holes are owned by ECAD"), "{}", error);
+        assert!(pins(editor.board()) > 0);
+
+        editor.set_placement_status("J1", "PLACED").unwrap();
+        let mut editor = BoardEditor::new(&mut board, Domain::Ecad);
+        editor.remove_placement("J1").unwrap();
+        assert_eq!(pins(editor.board()), 0);
+        let violations = HoleRuleSet {
+            rules: vec![HoleRule::UnknownPinPart],
+            config: HoleCheckConfig::default(),
+        }
+        .check(editor.board());
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn test_pin_holes_follow_placement() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let j1 = board
+            .component_placements
+            .iter()
+            .position(|p| p.reference_designator == "J1")
+            .unwrap();
+        board.component_placements[j1].placement_status = "PLACED".to_string();
+        let pins = |board: &BoardPanel| -> Vec<(f32, f32)> {
+            board
+                .drilled_holes
+                .iter()
+                .filter(|h| h.hole_type == "PIN" && h.associated_part == "J1")
+                .map(|h| (h.x, h.y))
+                .collect()
+        };
+        let before = pins(&board);
+        assert!(!before.is_empty());
+        let original = Transform::from_placement(&board.component_placements[j1]);
+        let (x, y) = (original.x, original.y);
+
+        // The holes are owned by ECAD, so MCAD may not move them along with J1.
+        let mut editor = BoardEditor::new(&mut board, Domain::Mcad);
+        let error = editor.move_placement("J1", x, y).unwrap_err();
+        assert!(error.contains("PIN holes are owned by ECAD"), "{}", error);
+
+        let mut editor = BoardEditor::new(&mut board, Domain::Ecad);
+        editor.move_placement("J1", x + 100.0, y - 50.0).unwrap();
+        let moved = pins(editor.board());
+        for (a, b) in before.iter().zip(moved.iter()) {
+            assert!((b.0 - a.0 - 100.0).abs() < 1e-3 && (b.1 - a.1 + 50.0).abs() < 1e-3);
+        }
+
+        // Rotating turns the holes about the placement, flipping mirrors them about its y axis.
+        editor
+            .rotate_placement("J1", original.rotation + 90.0)
+            .unwrap();
+        editor.flip_placement("J1").unwrap();
+        let transform = Transform::from_placement(&editor.board().component_placements[j1]);
+        for (a, b) in before.iter().zip(pins(editor.board()).iter()) {
+            let (local_x, local_y) = original.invert(a.0, a.1);
+            let expected = transform.apply(local_x, local_y);
+            assert!((b.0 - expected.0).abs() < 1e-2 && (b.1 - expected.1).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_ownership_is_enforced() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.component_placements[0].placement_status = "ECAD".to_string();
+        let mut editor = BoardEditor::new(&mut board, Domain::Mcad);
+
+        assert!(editor.move_placement("C1", 0.0, 0.0).is_err());
+        assert!(editor.remove_placement("C1").is_err());
+
+        // The sample board's holes are all owned by ECAD.
+        assert!(editor.remove_hole(0).is_err());
+        let hole = Hole {
+            diameter: 100.0,
+            x: 0.0,
+            y: 0.0,
+            plating_style: "NPTH".to_string(),
+            associated_part: "BOARD".to_string(),
+            hole_type: "MTG".to_string(),
+            owner: "ECAD".to_string(),
+        };
+        assert!(editor.add_hole(hole.clone()).is_err());
+        editor
+            .add_hole(Hole {
+                owner: "MCAD".to_string(),
+                ..hole.clone()
+            })
+            .unwrap();
+        let last = editor.board().drilled_holes.len() - 1;
+        editor.remove_hole(last).unwrap();
+        assert!(
+            editor
+                .add_hole(Hole {
+                    owner: "MCAD".to_string(),
+                    hole_type: "PIN".to_string(),
+                    associated_part: "U99".to_string(),
+                    ..hole
+                })
+                .is_err()
+        );
+
+        let keepout = editor.board().placement_keepouts[0].clone();
+        assert_eq!(keepout.owner, "MCAD");
+        editor.remove_placement_keepout(0).unwrap();
+        editor.add_placement_keepout(keepout).unwrap();
+
+        editor.add_note(Note::default());
+        let last = editor.board().notes.len() - 1;
+        editor.remove_note(last).unwrap();
+        assert!(editor.remove_note(last).is_err());
+    }
+}
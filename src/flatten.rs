@@ -0,0 +1,256 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::drilled_holes::Hole;
+use crate::geometry::{Transform, mm_per_unit};
+use crate::notes::Note;
+use crate::point::Point;
+
+/// A board instance of a panel with all of its entities in absolute panel coordinates.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct FlatBoard {
+    /// Index of the `BOARD` placement in the panel's component placements.
+    pub placement: usize,
+    /// The transform from board to panel coordinates.
+    pub transform: Transform,
+    /// A copy of the board transformed into panel coordinates and units.
+    pub board: BoardPanel,
+}
+
+/// A panel with every board instance expanded into panel coordinates.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct FlatPanel {
+    pub panel: BoardPanel,
+    pub boards: Vec<FlatBoard>,
+}
+
+impl FlatPanel {
+    /// The drilled holes of the panel followed by the holes of every board instance.
+    pub fn drilled_holes(&self) -> impl Iterator<Item = &Hole> {
+        self.panel.drilled_holes.iter().chain(
+            self.boards
+                .iter()
+                .flat_map(|b| b.board.drilled_holes.iter()),
+        )
+    }
+
+    /// The component placements of every board instance.
+    pub fn component_placements(&self) -> impl Iterator<Item = &ComponentPlacement> {
+        self.boards
+            .iter()
+            .flat_map(|b| b.board.component_placements.iter())
+    }
+}
+
+/// Swap TOP and BOTTOM, leaving other sides and layers such as BOTH unchanged.
+fn flip_side(side: &str) -> String {
+    match side {
+        "TOP" => "BOTTOM".to_string(),
+        "BOTTOM" => "TOP".to_string(),
+        _ => side.to_string(),
+    }
+}
+
+/// Copy a board with every entity transformed into the coordinates of its parent.
+///
+/// Lengths are converted from the units of the board to `units`. When the transform mirrors the
+/// board, entities on its TOP side end up on the BOTTOM side of the parent and vice versa.
+pub fn transform_board(board: &BoardPanel, transform: &Transform, units: &str) -> BoardPanel {
+    let scale = mm_per_unit(&board.header.units) / mm_per_unit(units);
+    let side = |side: &str| {
+        if transform.mirror {
+            flip_side(side)
+        } else {
+            side.to_string()
+        }
+    };
+    let point = |p: &Point| {
+        transform.apply_point(&Point {
+            x: p.x * scale,
+            y: p.y * scale,
+            ..p.clone()
+        })
+    };
+    let outline = |points: &[Point]| points.iter().map(point).collect::<Vec<_>>();
+
+    let mut flat = board.clone();
+    flat.header.units = units.to_string();
+
+    flat.outline.thickness *= scale;
+    flat.outline.outline = outline(&board.outline.outline);
+    for o in flat.other_outlines.iter_mut() {
+        o.extrude_thickness *= scale;
+        o.board_side = side(&o.board_side);
+        o.outline = outline(&o.outline);
+    }
+    for o in flat.routing_outlines.iter_mut() {
+        o.routing_layers = side(&o.routing_layers);
+        o.outline = outline(&o.outline);
+    }
+    for o in flat.placement_outlines.iter_mut() {
+        o.outline_height *= scale;
+        o.board_side = side(&o.board_side);
+        o.outline = outline(&o.outline);
+    }
+    for k in flat.routing_keepouts.iter_mut() {
+        k.routing_layers = side(&k.routing_layers);
+        k.outline = outline(&k.outline);
+    }
+    for k in flat.via_keepouts.iter_mut() {
+        k.outline = outline(&k.outline);
+    }
+    for k in flat.placement_keepouts.iter_mut() {
+        k.keepout_height *= scale;
+        k.board_side = side(&k.board_side);
+        k.outline = outline(&k.outline);
+    }
+    for a in flat.placement_group_areas.iter_mut() {
+        a.board_side = side(&a.board_side);
+        a.outline = outline(&a.outline);
+    }
+    for h in flat.drilled_holes.iter_mut() {
+        (h.x, h.y) = transform.apply(h.x * scale, h.y * scale);
+        h.diameter *= scale;
+    }
+    flat.notes = board
+        .notes
+        .iter()
+        .map(|n| {
+            let (x, y) = transform.apply(n.x * scale, n.y * scale);
+            Note {
+                x,
+                y,
+                text_height: n.text_height * scale,
+                test_string_physical_length: n.test_string_physical_length * scale,
+                text: n.text.clone(),
+            }
+        })
+        .collect();
+    for p in flat.component_placements.iter_mut() {
+        (p.x, p.y) = transform.apply(p.x * scale, p.y * scale);
+        p.mounting_offset *= scale;
+        p.rotation_angle = transform.apply_rotation(p.rotation_angle);
+        p.board_side = side(&p.board_side);
+    }
+    flat
+}
+
+/// Expand every `BOARD` placement of a panel into absolute panel coordinates.
+///
+/// Boards are matched to placements by the board name in their header. Each instance is a copy
+/// of its board with the outline, other outlines, routing and placement outlines, keepouts,
+/// holes, notes and component placements moved, rotated and mirrored by the placement.
+///
+/// # Example
+/// ```
+/// use idf_parser::flatten::flatten_panel;
+/// use idf_parser::parse_board_file;
+///
+/// let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let flat = flatten_panel(&panel, &[board]).unwrap();
+/// assert_eq!(flat.boards.len(), 2);
+/// assert_eq!(flat.boards[1].board.component_placements[0].board_side, "BOTTOM");
+/// ```
+pub fn flatten_panel(panel: &BoardPanel, boards: &[BoardPanel]) -> Result<FlatPanel, String> {
+    let mut flat_boards = Vec::new();
+    for (i, placement) in panel.component_placements.iter().enumerate() {
+        if placement.reference_designator != "BOARD" {
+            continue;
+        }
+        let board = boards
+            .iter()
+            .find(|b| b.header.board_name == placement.package_name)
+            .ok_or_else(|| {
+                format!(
+                    "Board {} placed on panel {} was not found.",
+                    placement.package_name, panel.header.board_name
+                )
+            })?;
+        let transform = Transform::from_placement(placement);
+        flat_boards.push(FlatBoard {
+            placement: i,
+            transform,
+            board: transform_board(board, &transform, &panel.header.units),
+        });
+    }
+    Ok(FlatPanel {
+        panel: panel.clone(),
+        boards: flat_boards,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_board_file;
+
+    fn flat() -> FlatPanel {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        flatten_panel(&panel, &[board]).unwrap()
+    }
+
+    #[test]
+    fn test_flatten_top_instance() {
+        let flat = flat();
+        let board = &flat.boards[0].board;
+        // The first instance is on the TOP at (1700, 3300) with no rotation.
+        assert_eq!(board.outline.outline[0].x, 5030.5 + 1700.0);
+        assert_eq!(board.outline.outline[0].y, -120.0 + 3300.0);
+        assert_eq!(board.outline.outline[4].angle, -180.0);
+        let c1 = &board.component_placements[0];
+        assert_eq!(
+            (c1.x, c1.y, c1.board_side.as_str()),
+            (5700.0, 4300.0, "TOP")
+        );
+    }
+
+    #[test]
+    fn test_flatten_bottom_instance() {
+        let flat = flat();
+        let board = &flat.boards[1].board;
+        // The second instance is mirrored, so x is negated before translating to (14000, 3300).
+        assert_eq!(board.outline.outline[0].x, 14000.0 - 5030.5);
+        assert_eq!(board.outline.outline[4].angle, 180.0);
+        let c1 = &board.component_placements[0];
+        assert_eq!(
+            (c1.x, c1.y, c1.board_side.as_str()),
+            (10000.0, 4300.0, "BOTTOM")
+        );
+        assert_eq!(board.placement_outlines[0].board_side, "BOTTOM");
+        assert_eq!(board.placement_outlines[1].board_side, "TOP");
+        assert_eq!(board.placement_keepouts[0].board_side, "BOTH");
+        assert_eq!(board.placement_keepouts[1].board_side, "BOTTOM");
+
+        let hole = &board.drilled_holes[0];
+        assert_eq!((hole.x, hole.y), (14000.0 - 1800.0, 3300.0 + 100.0));
+    }
+
+    #[test]
+    fn test_flatten_unit_conversion() {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.header.units = "MM".to_string();
+        let flat = flatten_panel(&panel, &[board]).unwrap();
+        let outline = &flat.boards[0].board.outline;
+        assert!((outline.thickness - 62.0 / 0.0254).abs() < 0.1);
+        assert_eq!(flat.boards[0].board.header.units, "THOU");
+    }
+
+    #[test]
+    fn test_flatten_missing_board() {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        assert!(flatten_panel(&panel, &[]).is_err());
+    }
+
+    #[test]
+    fn test_flat_panel_holes() {
+        let flat = flat();
+        let board_holes = flat.boards[0].board.drilled_holes.len();
+        assert_eq!(flat.drilled_holes().count(), 3 + 2 * board_holes);
+        assert_eq!(
+            flat.component_placements().count(),
+            2 * flat.boards[0].board.component_placements.len()
+        );
+    }
+}
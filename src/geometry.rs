@@ -0,0 +1,725 @@
+use crate::component_placement::ComponentPlacement;
+use crate::point::Point;
+use std::f32::consts::PI;
+
+/// A 2D vertex of a polygon once arcs have been resolved into line segments.
+pub type Vertex = (f32, f32);
+
+/// Number of millimetres in one unit of the given IDF unit string ("MM" or "THOU").
+///
+/// Unknown units are treated as millimetres.
+pub fn mm_per_unit(units: &str) -> f32 {
+    match units {
+        "THOU" => 0.0254,
+        _ => 1.0,
+    }
+}
+
+/// Axis aligned bounding box.
+#[derive(Debug, PartialEq, Clone, Copy, Default, PartialOrd)]
+pub struct BoundingBox {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl BoundingBox {
+    /// Create the bounding box of a set of vertices, returning None if there are none.
+    pub fn from_vertices<'a>(vertices: impl IntoIterator<Item = &'a Vertex>) -> Option<Self> {
+        let mut iter = vertices.into_iter();
+        let &(x, y) = iter.next()?;
+        let mut bbox = BoundingBox {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
+        };
+        for &(x, y) in iter {
+            bbox.include(x, y);
+        }
+        Some(bbox)
+    }
+
+    /// Grow the bounding box to include the given coordinate.
+    pub fn include(&mut self, x: f32, y: f32) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    /// Smallest bounding box containing both boxes.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+}
+
+/// The transform from the coordinates of a placed item to the coordinates of its parent.
+///
+/// IDF places an item by optionally mirroring it about its y axis (for BOTTOM side placements),
+/// then rotating it counter-clockwise by `rotation` degrees about its origin, then translating it
+/// to (`x`, `y`).
+#[derive(Debug, PartialEq, Clone, Copy, Default, PartialOrd)]
+pub struct Transform {
+    pub x: f32,
+    pub y: f32,
+    /// Counter-clockwise rotation in degrees.
+    pub rotation: f32,
+    /// Mirror about the y axis before rotating.
+    pub mirror: bool,
+}
+
+impl Transform {
+    /// The transform of a component or board placement.
+    pub fn from_placement(placement: &ComponentPlacement) -> Transform {
+        Transform {
+            x: placement.x,
+            y: placement.y,
+            rotation: placement.rotation_angle,
+            mirror: placement.board_side == "BOTTOM",
+        }
+    }
+
+    /// Transform a coordinate.
+    pub fn apply(&self, x: f32, y: f32) -> Vertex {
+        let x = if self.mirror { -x } else { x };
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (x * cos - y * sin + self.x, x * sin + y * cos + self.y)
+    }
+
+    /// Undo the transform of a coordinate, the inverse of [Transform::apply].
+    pub fn invert(&self, x: f32, y: f32) -> Vertex {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (x, y) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+        (if self.mirror { -x } else { x }, y)
+    }
+
+    /// Transform an outline point. Mirroring reverses the direction of arcs.
+    pub fn apply_point(&self, point: &Point) -> Point {
+        let (x, y) = self.apply(point.x, point.y);
+        Point {
+            loop_label: point.loop_label,
+            x,
+            y,
+            angle: if self.mirror {
+                -point.angle
+            } else {
+                point.angle
+            },
+        }
+    }
+
+    /// Transform a rotation angle in degrees, returning a value in [0, 360).
+    pub fn apply_rotation(&self, rotation: f32) -> f32 {
+        let rotation = if self.mirror { -rotation } else { rotation };
+        (self.rotation + rotation).rem_euclid(360.0)
+    }
+
+    /// The transform which applies `self` and then `outer`.
+    pub fn then(&self, outer: &Transform) -> Transform {
+        let (x, y) = outer.apply(self.x, self.y);
+        Transform {
+            x,
+            y,
+            rotation: outer.apply_rotation(self.rotation),
+            mirror: self.mirror != outer.mirror,
+        }
+    }
+}
+
+/// Split an outline into its loops.
+///
+/// Points belonging to the same loop share a loop label and are listed consecutively. The first
+/// loop of a board outline is the outline itself, subsequent loops are cutouts.
+pub fn split_loops(points: &[Point]) -> Vec<&[Point]> {
+    let mut loops = Vec::new();
+    let mut start = 0;
+    for i in 1..=points.len() {
+        if i == points.len() || points[i].loop_label != points[start].loop_label {
+            loops.push(&points[start..i]);
+            start = i;
+        }
+    }
+    loops
+}
+
+/// Centre and radius of the arc from `start` to `end` sweeping `angle` degrees, positive
+/// counter-clockwise.
+pub fn arc_centre(start: Vertex, end: Vertex, angle: f32) -> (Vertex, f32) {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let chord = (dx * dx + dy * dy).sqrt();
+    let half_angle = angle.to_radians() / 2.0;
+    let radius = chord / (2.0 * half_angle.sin().abs());
+    // Offset from the chord midpoint along the left hand normal of the chord.
+    let offset = (chord / 2.0) / half_angle.tan();
+    let (nx, ny) = (-dy / chord, dx / chord);
+    let centre = (
+        (start.0 + end.0) / 2.0 + nx * offset,
+        (start.1 + end.1) / 2.0 + ny * offset,
+    );
+    (centre, radius)
+}
+
+/// Number of straight segments needed for an arc of `sweep` degrees and `radius` so that the
+/// segments never deviate from the true arc by more than `tolerance`.
+fn arc_segments(sweep: f32, radius: f32, tolerance: f32) -> usize {
+    let max_step = if tolerance <= 0.0 || tolerance >= radius {
+        PI / 2.0
+    } else {
+        (2.0 * (1.0 - tolerance / radius).acos()).max(1e-3)
+    };
+    ((sweep.to_radians().abs() / max_step).ceil() as usize).clamp(1, 1024)
+}
+
+/// Whether a point's angle describes a full circle rather than an arc.
+pub fn is_full_circle(angle: f32) -> bool {
+    angle.abs() >= 359.999
+}
+
+/// Convert a single loop of IDF points into a polygon, replacing arcs with line segments which
+/// deviate from the true arc by no more than `tolerance`.
+///
+/// A loop of two points where the second has an angle of 360 is a circle centred on the first
+/// point passing through the second. The closing vertex is not repeated in the result.
+pub fn tessellate_loop(points: &[Point], tolerance: f32) -> Vec<Vertex> {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let Some(first) = points.first() else {
+        return vertices;
+    };
+
+    if let Some(end) = points.get(1).filter(|p| is_full_circle(p.angle)) {
+        let radius = ((end.x - first.x).powi(2) + (end.y - first.y).powi(2)).sqrt();
+        let start_angle = (end.y - first.y).atan2(end.x - first.x);
+        let segments = arc_segments(360.0, radius, tolerance).max(8);
+        return (0..segments)
+            .map(|i| {
+                let a = start_angle + 2.0 * PI * i as f32 / segments as f32;
+                (first.x + radius * a.cos(), first.y + radius * a.sin())
+            })
+            .collect();
+    }
+
+    vertices.push((first.x, first.y));
+    for pair in points.windows(2) {
+        let (start, end) = ((pair[0].x, pair[0].y), (pair[1].x, pair[1].y));
+        if pair[1].angle.abs() > 1e-6 && start != end {
+            let (centre, radius) = arc_centre(start, end, pair[1].angle);
+            let start_angle = (start.1 - centre.1).atan2(start.0 - centre.0);
+            let segments = arc_segments(pair[1].angle, radius, tolerance);
+            for i in 1..segments {
+                let a = start_angle + pair[1].angle.to_radians() * i as f32 / segments as f32;
+                vertices.push((centre.0 + radius * a.cos(), centre.1 + radius * a.sin()));
+            }
+        }
+        vertices.push(end);
+    }
+
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+    vertices
+}
+
+/// Signed area of a closed ring, positive when counter-clockwise.
+pub fn signed_area(ring: &[Vertex]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..ring.len() {
+        let (x1, y1) = ring[i];
+        let (x2, y2) = ring[(i + 1) % ring.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    area / 2.0
+}
+
+/// Whether a point lies within a closed ring, using the even-odd rule.
+pub fn ring_contains(ring: &[Vertex], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let (xi, yi) = ring[i];
+        let (xj, yj) = ring[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Shortest distance from a point to the line segment between `a` and `b`.
+pub fn distance_to_segment(x: f32, y: f32, a: Vertex, b: Vertex) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((x - a.0) * dx + (y - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    let (px, py) = (a.0 + t * dx, a.1 + t * dy);
+    ((x - px).powi(2) + (y - py).powi(2)).sqrt()
+}
+
+/// Shortest distance from a point to the edges of a closed ring.
+pub fn distance_to_ring(ring: &[Vertex], x: f32, y: f32) -> f32 {
+    (0..ring.len())
+        .map(|i| distance_to_segment(x, y, ring[i], ring[(i + 1) % ring.len()]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Whether the segment from `a` to `b` properly crosses the segment from `c` to `d`. Segments
+/// which only touch or are collinear do not cross.
+pub fn segments_cross(a: Vertex, b: Vertex, c: Vertex, d: Vertex) -> bool {
+    let cross =
+        |o: Vertex, p: Vertex, q: Vertex| (p.0 - o.0) * (q.1 - o.1) - (p.1 - o.1) * (q.0 - o.0);
+    let straddles = |d1: f32, d2: f32| (d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0);
+    straddles(cross(c, d, a), cross(c, d, b)) && straddles(cross(a, b, c), cross(a, b, d))
+}
+
+/// The edges of a closed ring as pairs of vertices.
+fn ring_edges(ring: &[Vertex]) -> impl Iterator<Item = (Vertex, Vertex)> + '_ {
+    (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+/// A polygon made up of an outer ring and zero or more holes (cutouts).
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct Polygon {
+    pub outer: Vec<Vertex>,
+    pub holes: Vec<Vec<Vertex>>,
+}
+
+impl Polygon {
+    /// Build a polygon from an IDF outline, tessellating arcs to within `tolerance`.
+    ///
+    /// The loop enclosing the largest area is taken as the outer ring and all other loops as
+    /// cutouts. Loops with fewer than three vertices are ignored.
+    pub fn from_outline(points: &[Point], tolerance: f32) -> Polygon {
+        let mut rings: Vec<Vec<Vertex>> = split_loops(points)
+            .into_iter()
+            .map(|l| tessellate_loop(l, tolerance))
+            .filter(|ring| ring.len() >= 3)
+            .collect();
+
+        let Some(outer_index) = (0..rings.len()).max_by(|&a, &b| {
+            signed_area(&rings[a])
+                .abs()
+                .total_cmp(&signed_area(&rings[b]).abs())
+        }) else {
+            return Polygon::default();
+        };
+        let outer = rings.remove(outer_index);
+        Polygon {
+            outer,
+            holes: rings,
+        }
+    }
+
+    /// Whether the polygon has enough vertices to enclose an area.
+    pub fn is_empty(&self) -> bool {
+        self.outer.len() < 3
+    }
+
+    /// Whether the point lies inside the outer ring and outside every hole.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        ring_contains(&self.outer, x, y) && !self.holes.iter().any(|h| ring_contains(h, x, y))
+    }
+
+    /// Area of the outer ring less the area of the holes.
+    pub fn area(&self) -> f32 {
+        signed_area(&self.outer).abs()
+            - self.holes.iter().map(|h| signed_area(h).abs()).sum::<f32>()
+    }
+
+    /// Shortest distance from a point to any edge of the polygon, including holes.
+    pub fn distance_to_boundary(&self, x: f32, y: f32) -> f32 {
+        self.holes
+            .iter()
+            .map(|h| distance_to_ring(h, x, y))
+            .fold(distance_to_ring(&self.outer, x, y), f32::min)
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_vertices(&self.outer)
+    }
+
+    /// The outer ring followed by the holes.
+    pub fn rings(&self) -> impl Iterator<Item = &Vec<Vertex>> {
+        std::iter::once(&self.outer).chain(self.holes.iter())
+    }
+
+    /// Distance below which a point is considered to be on the boundary, allowing for the
+    /// rounding of f32 coordinates.
+    fn epsilon(&self) -> f32 {
+        self.bounding_box()
+            .map_or(0.0, |b| b.width().max(b.height()))
+            * 1e-5
+    }
+
+    /// Whether the point lies inside the polygon and not on its boundary.
+    fn strictly_contains(&self, (x, y): Vertex) -> bool {
+        self.contains(x, y) && self.distance_to_boundary(x, y) > self.epsilon()
+    }
+
+    /// Whether any vertex or edge midpoint of `other` lies strictly inside this polygon.
+    fn contains_any_of(&self, other: &Polygon) -> bool {
+        other
+            .rings()
+            .flat_map(|ring| ring_edges(ring))
+            .any(|(a, b)| {
+                let mid = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                self.strictly_contains(a) || self.strictly_contains(mid)
+            })
+    }
+
+    /// Whether the two polygons share any area. Polygons which only touch do not overlap.
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return false;
+        }
+        let crossing = self.rings().any(|a| {
+            other.rings().any(|b| {
+                ring_edges(a).any(|(p, q)| ring_edges(b).any(|(r, s)| segments_cross(p, q, r, s)))
+            })
+        });
+        crossing
+            || self.contains_any_of(other)
+            || other.contains_any_of(self)
+            || self.encloses(other)
+    }
+
+    /// Whether `other` lies entirely within this polygon, touching the boundary being allowed.
+    ///
+    /// Vertices are compared, so an edge of this polygon which passes through `other` without
+    /// either of its ends or its midpoint lying inside `other` is not detected.
+    pub fn encloses(&self, other: &Polygon) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return false;
+        }
+        other
+            .outer
+            .iter()
+            .all(|&(x, y)| self.contains(x, y) || self.distance_to_boundary(x, y) <= self.epsilon())
+            && !other.contains_any_of(self)
+    }
+
+    /// Shortest distance between the boundaries of two polygons, zero if they overlap.
+    pub fn distance_to_polygon(&self, other: &Polygon) -> f32 {
+        if self.overlaps(other) {
+            return 0.0;
+        }
+        let one_way = |a: &Polygon, b: &Polygon| {
+            a.rings()
+                .flatten()
+                .map(|&(x, y)| b.distance_to_boundary(x, y))
+                .fold(f32::INFINITY, f32::min)
+        };
+        one_way(self, other).min(one_way(other, self))
+    }
+
+    /// Split the polygon into counter-clockwise triangles by ear clipping.
+    ///
+    /// Each triangle indexes the vertices of [rings](Polygon::rings) taken in order, so the
+    /// outer ring's vertices come first followed by those of each hole. Holes are joined to the
+    /// outer ring by a bridge from their rightmost vertex before clipping.
+    pub fn triangulate(&self) -> Vec<[usize; 3]> {
+        let vertices: Vec<Vertex> = self.rings().flatten().copied().collect();
+        let cross = |o: usize, a: usize, b: usize| {
+            let (o, a, b) = (vertices[o], vertices[a], vertices[b]);
+            (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+        };
+
+        // Vertex indices of each ring, the outer counter-clockwise and the holes clockwise.
+        let mut start = 0;
+        let mut rings: Vec<Vec<usize>> = Vec::new();
+        for (i, ring) in self.rings().enumerate() {
+            let mut indices: Vec<usize> = (start..start + ring.len()).collect();
+            if (signed_area(ring) > 0.0) != (i == 0) {
+                indices.reverse();
+            }
+            start += ring.len();
+            rings.push(indices);
+        }
+        let mut chain = rings.remove(0);
+        if chain.len() < 3 {
+            return vec![];
+        }
+        rings.sort_by(|a, b| {
+            let max_x = |ring: &Vec<usize>| {
+                ring.iter()
+                    .map(|&i| vertices[i].0)
+                    .fold(f32::NEG_INFINITY, f32::max)
+            };
+            max_x(b).total_cmp(&max_x(a))
+        });
+
+        // Whether the direction from chain[i] to `target` points into the polygon.
+        let inside_corner = |chain: &[usize], i: usize, target: usize| {
+            let n = chain.len();
+            let (prev, vertex, next) = (chain[(i + n - 1) % n], chain[i], chain[(i + 1) % n]);
+            if cross(prev, vertex, next) >= 0.0 {
+                cross(vertex, next, target) >= 0.0 && cross(vertex, target, prev) >= 0.0
+            } else {
+                cross(vertex, prev, target) <= 0.0 || cross(vertex, target, next) <= 0.0
+            }
+        };
+        for (h, hole) in rings.iter().enumerate() {
+            let (m, &from) = hole
+                .iter()
+                .enumerate()
+                .max_by(|a, b| vertices[*a.1].0.total_cmp(&vertices[*b.1].0))
+                .unwrap();
+            let crosses = |ring: &[usize], to: usize| {
+                (0..ring.len()).any(|k| {
+                    let (c, d) = (ring[k], ring[(k + 1) % ring.len()]);
+                    segments_cross(vertices[from], vertices[to], vertices[c], vertices[d])
+                })
+            };
+            let distance = |to: usize| {
+                let (a, b) = (vertices[from], vertices[to]);
+                (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+            };
+            let bridge = (0..chain.len())
+                .filter(|&i| inside_corner(&chain, i, from))
+                .filter(|&i| !crosses(&chain, chain[i]))
+                .filter(|&i| !rings[h..].iter().any(|ring| crosses(ring, chain[i])))
+                .min_by(|&a, &b| distance(chain[a]).total_cmp(&distance(chain[b])));
+            let Some(i) = bridge else {
+                continue;
+            };
+            let mut spliced: Vec<usize> = chain[..=i].to_vec();
+            spliced.extend(hole[m..].iter().chain(&hole[..=m]));
+            spliced.push(chain[i]);
+            spliced.extend(&chain[i + 1..]);
+            chain = spliced;
+        }
+
+        let mut triangles = Vec::new();
+        let mut i = 0;
+        let mut attempts = 0;
+        while chain.len() > 3 {
+            let n = chain.len();
+            let (prev, vertex, next) = (chain[(i + n - 1) % n], chain[i], chain[(i + 1) % n]);
+            let corners = [vertices[prev], vertices[vertex], vertices[next]];
+            let is_ear = cross(prev, vertex, next) > 0.0
+                && !chain.iter().any(|&p| {
+                    !corners.contains(&vertices[p])
+                        && cross(prev, vertex, p) >= 0.0
+                        && cross(vertex, next, p) >= 0.0
+                        && cross(next, prev, p) >= 0.0
+                });
+            // If no ear is found in a full pass the remaining chain is degenerate, so clip
+            // regardless to guarantee progress.
+            if is_ear || attempts > n {
+                if cross(prev, vertex, next) > 0.0 {
+                    triangles.push([prev, vertex, next]);
+                }
+                chain.remove(i);
+                attempts = 0;
+                // Step back so the previous vertex, whose corner has changed, is tried next.
+                i = if i == 0 { n - 2 } else { i - 1 };
+            } else {
+                i = (i + 1) % n;
+                attempts += 1;
+            }
+        }
+        if cross(chain[0], chain[1], chain[2]) > 0.0 {
+            triangles.push([chain[0], chain[1], chain[2]]);
+        }
+        triangles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(loop_label: u32, x: f32, y: f32, angle: f32) -> Point {
+        Point {
+            loop_label,
+            x,
+            y,
+            angle,
+        }
+    }
+
+    #[test]
+    fn test_split_loops() {
+        let points = vec![
+            point(0, 0.0, 0.0, 0.0),
+            point(0, 1.0, 0.0, 0.0),
+            point(1, 2.0, 2.0, 0.0),
+            point(1, 3.0, 2.0, 360.0),
+        ];
+        let loops = split_loops(&points);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].len(), 2);
+        assert_eq!(loops[1][0].x, 2.0);
+    }
+
+    #[test]
+    fn test_arc_centre() {
+        // Quarter circle counter-clockwise from (1, 0) to (0, 1) about the origin.
+        let (centre, radius) = arc_centre((1.0, 0.0), (0.0, 1.0), 90.0);
+        assert!(centre.0.abs() < 1e-5 && centre.1.abs() < 1e-5);
+        assert!((radius - 1.0).abs() < 1e-5);
+
+        // The same chord clockwise puts the centre on the other side.
+        let (centre, _) = arc_centre((1.0, 0.0), (0.0, 1.0), -90.0);
+        assert!((centre.0 - 1.0).abs() < 1e-5 && (centre.1 - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_transform() {
+        let transform = Transform {
+            x: 10.0,
+            y: 0.0,
+            rotation: 90.0,
+            mirror: true,
+        };
+        let (x, y) = transform.apply(1.0, 2.0);
+        assert!((x - 8.0).abs() < 1e-5 && (y + 1.0).abs() < 1e-5);
+        assert_eq!(
+            transform.apply_point(&point(0, 1.0, 2.0, 90.0)).angle,
+            -90.0
+        );
+        assert_eq!(transform.apply_rotation(30.0), 60.0);
+
+        // Composing matches applying each transform in turn.
+        let inner = Transform {
+            x: 3.0,
+            y: 4.0,
+            rotation: 45.0,
+            mirror: false,
+        };
+        let composed = inner.then(&transform);
+        let (ix, iy) = inner.apply(5.0, 6.0);
+        let expected = transform.apply(ix, iy);
+        let actual = composed.apply(5.0, 6.0);
+        assert!((expected.0 - actual.0).abs() < 1e-4 && (expected.1 - actual.1).abs() < 1e-4);
+        assert!(composed.mirror);
+
+        let (x, y) = transform.invert(8.0, -1.0);
+        assert!((x - 1.0).abs() < 1e-5 && (y - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_tessellate_circle() {
+        let points = vec![point(1, 10.0, 10.0, 0.0), point(1, 15.0, 10.0, 360.0)];
+        let ring = tessellate_loop(&points, 0.01);
+        assert!(ring.len() > 8);
+        for (x, y) in ring {
+            let r = ((x - 10.0).powi(2) + (y - 10.0).powi(2)).sqrt();
+            assert!((r - 5.0).abs() < 1e-3);
+        }
+    }
+
+    fn square(x: f32, y: f32, size: f32) -> Polygon {
+        Polygon {
+            outer: vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)],
+            holes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_polygon_relations() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(15.0, 0.0, 10.0);
+        let c = square(5.0, 5.0, 10.0);
+        let d = square(2.0, 2.0, 2.0);
+        assert!(!a.overlaps(&b));
+        assert!((a.distance_to_polygon(&b) - 5.0).abs() < 1e-5);
+        assert!(a.overlaps(&c));
+        assert_eq!(a.distance_to_polygon(&c), 0.0);
+        assert!(a.overlaps(&d));
+        assert!(a.encloses(&d));
+        assert!(a.encloses(&a));
+        assert!(!a.encloses(&c));
+
+        let mut with_hole = square(0.0, 0.0, 10.0);
+        with_hole.holes.push(square(4.0, 4.0, 2.0).outer);
+        assert!(!with_hole.encloses(&square(3.0, 3.0, 4.0)));
+        assert!(!with_hole.overlaps(&square(4.5, 4.5, 1.0)));
+
+        // Polygons which only share an edge touch but do not overlap.
+        let touching = square(10.0, 0.0, 10.0);
+        assert!(!a.overlaps(&touching));
+        assert_eq!(a.distance_to_polygon(&touching), 0.0);
+    }
+
+    #[test]
+    fn test_polygon_with_cutout() {
+        let points = vec![
+            point(0, 0.0, 0.0, 0.0),
+            point(0, 100.0, 0.0, 0.0),
+            point(0, 100.0, 100.0, 0.0),
+            point(0, 0.0, 100.0, 0.0),
+            point(0, 0.0, 0.0, 0.0),
+            point(1, 50.0, 50.0, 0.0),
+            point(1, 60.0, 50.0, 360.0),
+        ];
+        let polygon = Polygon::from_outline(&points, 0.01);
+        assert_eq!(polygon.outer.len(), 4);
+        assert_eq!(polygon.holes.len(), 1);
+        assert!(polygon.contains(10.0, 10.0));
+        assert!(!polygon.contains(50.0, 50.0));
+        assert!(!polygon.contains(150.0, 50.0));
+        assert!((polygon.area() - (10000.0 - PI * 100.0)).abs() < 1.0);
+        assert!((polygon.distance_to_boundary(5.0, 50.0) - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_triangulate() {
+        let triangle_area = |polygon: &Polygon| {
+            let vertices: Vec<Vertex> = polygon.rings().flatten().copied().collect();
+            polygon
+                .triangulate()
+                .iter()
+                .map(|t| signed_area(&[vertices[t[0]], vertices[t[1]], vertices[t[2]]]))
+                .inspect(|&area| assert!(area > 0.0))
+                .sum::<f32>()
+        };
+
+        // A clockwise L shape.
+        let l_shape = Polygon {
+            outer: vec![
+                (0.0, 0.0),
+                (0.0, 20.0),
+                (10.0, 20.0),
+                (10.0, 10.0),
+                (20.0, 10.0),
+                (20.0, 0.0),
+            ],
+            holes: vec![],
+        };
+        assert_eq!(l_shape.triangulate().len(), 4);
+        assert!((triangle_area(&l_shape) - 300.0).abs() < 1e-3);
+
+        let mut with_holes = square(0.0, 0.0, 100.0);
+        with_holes.holes.push(square(10.0, 10.0, 20.0).outer);
+        with_holes.holes.push(square(60.0, 10.0, 20.0).outer);
+        let mut circle = tessellate_loop(
+            &[point(2, 50.0, 70.0, 0.0), point(2, 60.0, 70.0, 360.0)],
+            0.01,
+        );
+        circle.reverse();
+        with_holes.holes.push(circle.clone());
+        let expected = 10000.0 - 800.0 + signed_area(&circle);
+        assert!((triangle_area(&with_holes) - expected).abs() < 1e-2);
+        let vertex_count = 12 + circle.len();
+        assert_eq!(with_holes.triangulate().len(), vertex_count + 2 * 3 - 2);
+    }
+}
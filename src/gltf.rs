@@ -0,0 +1,288 @@
+use crate::board::BoardPanel;
+use crate::library::Library;
+use crate::solids::board_extrusions;
+use crate::validation::{json_number, json_string};
+
+/// glTF component types of the accessors.
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Rotation taking the board's z up coordinates to glTF's y up, as an x, y, z, w quaternion.
+const Z_UP_TO_Y_UP: &str = "[-0.70710677,0,0,0.70710677]";
+
+/// Encode bytes as standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// The glTF document without its buffer, and the binary buffer it refers to.
+///
+/// Every solid gets a node with a mesh of one primitive, whose positions and indices are an
+/// accessor and buffer view each. The board solid's node comes first.
+fn build(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> (String, Vec<u8>) {
+    let index = library.map(|l| l.index());
+    let mut binary: Vec<u8> = Vec::new();
+    let (mut nodes, mut meshes, mut accessors, mut views) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+
+    let solids = board_extrusions(board, library, tolerance)
+        .into_iter()
+        .filter(|s| !s.polygon.is_empty() && s.top > s.bottom);
+    for solid in solids {
+        let mesh = solid.mesh();
+        let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+        let start = binary.len();
+        for vertex in &mesh.vertices {
+            for k in 0..3 {
+                min[k] = min[k].min(vertex[k]);
+                max[k] = max[k].max(vertex[k]);
+                binary.extend_from_slice(&vertex[k].to_le_bytes());
+            }
+        }
+        views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34962}}",
+            start,
+            binary.len() - start
+        ));
+        let start = binary.len();
+        for &i in mesh.triangles.iter().flatten() {
+            binary.extend_from_slice(&(i as u32).to_le_bytes());
+        }
+        views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}",
+            start,
+            binary.len() - start
+        ));
+
+        let bounds = |v: [f32; 3]| {
+            let v: Vec<String> = v.iter().map(|&x| json_number(x)).collect();
+            format!("[{}]", v.join(","))
+        };
+        accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"VEC3\",\"min\":{},\"max\":{}}}",
+            views.len() - 2,
+            FLOAT,
+            mesh.vertices.len(),
+            bounds(min),
+            bounds(max)
+        ));
+        accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+            views.len() - 1,
+            UNSIGNED_INT,
+            mesh.triangles.len() * 3
+        ));
+        let material = if solid.placement.is_some() { 1 } else { 0 };
+        meshes.push(format!(
+            "{{\"name\":{},\"primitives\":[{{\"attributes\":{{\"POSITION\":{}}},\"indices\":{},\"material\":{}}}]}}",
+            json_string(&solid.name),
+            accessors.len() - 2,
+            accessors.len() - 1,
+            material
+        ));
+
+        let mut extras = Vec::new();
+        match solid.placement.map(|i| &board.component_placements[i]) {
+            Some(placement) => {
+                extras.push(format!(
+                    "\"part_number\":{}",
+                    json_string(&placement.part_number)
+                ));
+                extras.push(format!(
+                    "\"package_name\":{}",
+                    json_string(&placement.package_name)
+                ));
+                extras.push(format!(
+                    "\"placement_status\":{}",
+                    json_string(&placement.placement_status)
+                ));
+                extras.push(format!(
+                    "\"board_side\":{}",
+                    json_string(&placement.board_side)
+                ));
+                let properties = index
+                    .as_ref()
+                    .and_then(|index| index.for_placement(placement))
+                    .and_then(|component| component.properties());
+                let mut properties: Vec<(&String, &f32)> =
+                    properties.into_iter().flatten().collect();
+                properties.sort_by(|a, b| a.0.cmp(b.0));
+                let properties: Vec<String> = properties
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", json_string(key), json_number(**value)))
+                    .collect();
+                extras.push(format!("\"properties\":{{{}}}", properties.join(",")));
+            }
+            None => extras.push(format!(
+                "\"thickness\":{}",
+                json_number(solid.top - solid.bottom)
+            )),
+        }
+        nodes.push(format!(
+            "{{\"name\":{},\"mesh\":{},\"extras\":{{{}}}}}",
+            json_string(&solid.name),
+            meshes.len() - 1,
+            extras.join(",")
+        ));
+    }
+
+    // A root node turns the millimetre, z up solids into glTF's metres and y up.
+    let children: Vec<String> = (0..nodes.len()).map(|i| i.to_string()).collect();
+    nodes.push(format!(
+        "{{\"name\":{},\"rotation\":{},\"scale\":[0.001,0.001,0.001],\"children\":[{}]}}",
+        json_string(&board.header.board_name),
+        Z_UP_TO_Y_UP,
+        children.join(",")
+    ));
+
+    let json = format!(
+        "\"asset\":{{\"version\":\"2.0\",\"generator\":\"idf_parser\"}},\"scene\":0,\
+         \"scenes\":[{{\"nodes\":[{}]}}],\"nodes\":[{}],\"meshes\":[{}],\
+         \"materials\":[{{\"name\":\"board\",\"pbrMetallicRoughness\":{{\"baseColorFactor\":[0.1,0.45,0.15,1],\"metallicFactor\":0,\"roughnessFactor\":0.8}}}},\
+         {{\"name\":\"component\",\"pbrMetallicRoughness\":{{\"baseColorFactor\":[0.2,0.2,0.2,1],\"metallicFactor\":0,\"roughnessFactor\":0.6}}}}],\
+         \"accessors\":[{}],\"bufferViews\":[{}]",
+        nodes.len() - 1,
+        nodes.join(","),
+        meshes.join(","),
+        accessors.join(","),
+        views.join(",")
+    );
+    (json, binary)
+}
+
+/// Write the board and its placed components as a glTF 2.0 document with an embedded buffer.
+///
+/// The solids are those of [board_extrusions](crate::solids::board_extrusions), with arcs
+/// replaced by facets deviating from them by no more than `tolerance` millimetres. The scene
+/// has a root node named after the board, which scales the millimetre geometry to metres and
+/// turns the board's z axis to glTF's y axis. Its children are the board and one node per
+/// placed component, named after the reference designator, with `extras` holding:
+///
+/// ```text
+/// {"part_number":"pn-cap","package_name":"cs13_a","placement_status":"PLACED",
+///  "board_side":"TOP","properties":{"CAPACITANCE":100,"TOLERANCE":5}}
+/// ```
+///
+/// The board's node instead has the board thickness in millimetres as `{"thickness":1.5748}`.
+///
+/// # Example
+/// ```
+/// use idf_parser::gltf::write_gltf;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let gltf = write_gltf(&board, Some(&library), 0.05);
+/// assert!(gltf.contains("\"name\":\"C1\""));
+/// ```
+pub fn write_gltf(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> String {
+    let (json, binary) = build(board, library, tolerance);
+    format!(
+        "{{{},\"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}]}}",
+        json,
+        binary.len(),
+        base64(&binary)
+    )
+}
+
+/// Write the board and its placed components as a binary glTF (GLB) file.
+///
+/// The document is the same as that of [write_gltf], with the buffer stored in the file's
+/// binary chunk rather than embedded as base64.
+///
+/// # Example
+/// ```
+/// use idf_parser::gltf::write_glb;
+/// use idf_parser::parse_board_file;
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let glb = write_glb(&board, None, 0.05);
+/// assert_eq!(&glb[0..4], b"glTF");
+/// ```
+pub fn write_glb(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> Vec<u8> {
+    let (json, mut binary) = build(board, library, tolerance);
+    let mut json = format!(
+        "{{{},\"buffers\":[{{\"byteLength\":{}}}]}}",
+        json,
+        binary.len()
+    )
+    .into_bytes();
+    // Chunks are padded to four bytes, JSON with spaces and binary data with zeros.
+    json.resize(json.len().next_multiple_of(4), b' ');
+    binary.resize(binary.len().next_multiple_of(4), 0);
+
+    let mut output = Vec::with_capacity(28 + json.len() + binary.len());
+    output.extend_from_slice(b"glTF");
+    output.extend_from_slice(&2u32.to_le_bytes());
+    output.extend_from_slice(&((28 + json.len() + binary.len()) as u32).to_le_bytes());
+    output.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    output.extend_from_slice(b"JSON");
+    output.extend_from_slice(&json);
+    output.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+    output.extend_from_slice(b"BIN\0");
+    output.extend_from_slice(&binary);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_gltf_and_glb() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+
+        let gltf = write_gltf(&board, Some(&library), 0.05);
+        assert!(gltf.contains(
+            "\"name\":\"C1\",\"mesh\":1,\"extras\":{\"part_number\":\"pn-cap\",\"package_name\":\"cs13_a\",\"placement_status\":\"PLACED\",\"board_side\":\"TOP\",\"properties\":{\"CAPACITANCE\":100,\"TOLERANCE\":5}}"
+        ));
+        assert!(
+            gltf.contains(
+                "{\"name\":\"sample_board\",\"mesh\":0,\"extras\":{\"thickness\":1.5748}}"
+            )
+        );
+        let nodes = 2 + board.component_placements.len();
+        assert!(gltf.contains(&format!("\"scenes\":[{{\"nodes\":[{}]}}]", nodes - 1)));
+
+        let glb = write_glb(&board, Some(&library), 0.05);
+        let word = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+        assert_eq!(word(4), 2);
+        assert_eq!(word(8) as usize, glb.len());
+        let json_length = word(12) as usize;
+        assert_eq!(json_length % 4, 0);
+        assert_eq!(&glb[16..20], b"JSON");
+        let json = std::str::from_utf8(&glb[20..20 + json_length]).unwrap();
+        assert!(json.trim_end().ends_with("}]}"));
+        assert_eq!(&glb[24 + json_length..28 + json_length], b"BIN\0");
+        assert_eq!(
+            word(20 + json_length) as usize,
+            glb.len() - 28 - json_length
+        );
+    }
+}
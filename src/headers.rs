@@ -11,6 +11,8 @@ use nom::error::ErrorKind;
 use nom::{IResult, error};
 
 #[derive(PartialEq, Debug, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LibraryHeader {
     pub version: u32, // which IDF version, should be 3.0
     pub system_id: String,
@@ -19,6 +21,8 @@ pub struct LibraryHeader {
 }
 
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BoardPanelHeader {
     pub file_type: String, // BOARD_FILE or PANEL_FILE
     pub version: u32,      // which IDF version, should be 3.0
@@ -29,6 +33,44 @@ pub struct BoardPanelHeader {
     pub units: String,
 }
 
+/// The current UTC time formatted as an IDF header date, `yyyy/mm/dd.hh:mm:ss`.
+pub fn current_date() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format_date(seconds)
+}
+
+/// Format seconds since the unix epoch as an IDF header date.
+fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Convert days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}/{:02}/{:02}.{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    )
+}
+
 /// Parses the first line of the header section.
 fn header_metadata(input: &str) -> IResult<&str, (String, u32, String, String, u32)> {
     let (remaining, (file_type, version, system_id, date, file_version)) = (
@@ -143,6 +185,13 @@ mod tests {
         assert_eq!(file_version, 1);
     }
 
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970/01/01.00:00:00");
+        assert_eq!(format_date(1271950166), "2010/04/22.15:29:26");
+        assert_eq!(format_date(951782400), "2000/02/29.00:00:00");
+    }
+
     #[test]
     fn test_parse_board_header() {
         let input = ".HEADER
@@ -0,0 +1,432 @@
+use crate::board::BoardPanel;
+use crate::drilled_holes::Hole;
+use crate::geometry::{Polygon, mm_per_unit, ring_contains};
+use std::collections::HashSet;
+
+/// Design rules which can be checked against the drilled holes of a board or panel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum HoleRule {
+    /// The hole centre lies outside the board or panel outline.
+    OutsideOutline,
+    /// The hole centre lies inside a cutout of the board or panel outline.
+    InsideCutout,
+    /// Two holes with the same diameter share the same centre.
+    Duplicate,
+    /// Two holes with different diameters share the same centre.
+    Coincident,
+    /// The gap between the edges of two holes is below the minimum clearance.
+    HoleToHoleClearance,
+    /// The gap between the edge of a hole and the board edge or a cutout is below the minimum
+    /// clearance.
+    HoleToEdgeClearance,
+    /// A VIA hole overlaps a via keepout region.
+    ViaInKeepout,
+    /// A PIN hole is associated with a reference designator that is not placed on the board.
+    UnknownPinPart,
+}
+
+impl HoleRule {
+    /// Every hole rule, in the order they are checked.
+    pub const ALL: [HoleRule; 8] = [
+        HoleRule::OutsideOutline,
+        HoleRule::InsideCutout,
+        HoleRule::Duplicate,
+        HoleRule::Coincident,
+        HoleRule::HoleToHoleClearance,
+        HoleRule::HoleToEdgeClearance,
+        HoleRule::ViaInKeepout,
+        HoleRule::UnknownPinPart,
+    ];
+
+    /// A short stable identifier for the rule.
+    pub fn id(&self) -> &'static str {
+        match self {
+            HoleRule::OutsideOutline => "hole.outside_outline",
+            HoleRule::InsideCutout => "hole.inside_cutout",
+            HoleRule::Duplicate => "hole.duplicate",
+            HoleRule::Coincident => "hole.coincident",
+            HoleRule::HoleToHoleClearance => "hole.hole_to_hole_clearance",
+            HoleRule::HoleToEdgeClearance => "hole.hole_to_edge_clearance",
+            HoleRule::ViaInKeepout => "hole.via_in_keepout",
+            HoleRule::UnknownPinPart => "hole.unknown_pin_part",
+        }
+    }
+}
+
+/// Thresholds used by the hole rules. All values are in millimetres and are converted to the
+/// units of the board being checked.
+#[derive(Debug, PartialEq, Clone, Copy, PartialOrd)]
+pub struct HoleCheckConfig {
+    /// Minimum gap between the edges of two holes.
+    pub min_hole_to_hole: f32,
+    /// Minimum gap between the edge of a hole and the board edge or a cutout.
+    pub min_hole_to_edge: f32,
+    /// Hole centres closer than this are considered to be at the same position.
+    pub position_tolerance: f32,
+}
+
+impl Default for HoleCheckConfig {
+    fn default() -> Self {
+        HoleCheckConfig {
+            min_hole_to_hole: 0.25,
+            min_hole_to_edge: 0.25,
+            position_tolerance: 0.001,
+        }
+    }
+}
+
+/// A single breach of a hole rule.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct HoleViolation {
+    pub rule: HoleRule,
+    /// Indices into `BoardPanel::drilled_holes` of the holes involved.
+    pub holes: Vec<usize>,
+    /// Position of the (first) offending hole, in board units.
+    pub x: f32,
+    pub y: f32,
+    pub message: String,
+}
+
+/// A reusable set of hole rules together with the thresholds they are checked against.
+///
+/// # Example
+/// ```
+/// use idf_parser::parse_board_file;
+/// use idf_parser::hole_checks::{HoleRule, HoleRuleSet};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+///
+/// let mut rules = HoleRuleSet::default();
+/// rules.rules.retain(|rule| *rule != HoleRule::HoleToEdgeClearance);
+/// let violations = rules.check(&board);
+/// ```
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct HoleRuleSet {
+    pub rules: Vec<HoleRule>,
+    pub config: HoleCheckConfig,
+}
+
+impl Default for HoleRuleSet {
+    fn default() -> Self {
+        HoleRuleSet {
+            rules: HoleRule::ALL.to_vec(),
+            config: HoleCheckConfig::default(),
+        }
+    }
+}
+
+impl HoleRuleSet {
+    /// Check every rule in the set against the drilled holes of a board or panel.
+    pub fn check(&self, board: &BoardPanel) -> Vec<HoleViolation> {
+        let mut violations = Vec::new();
+        for rule in self.rules.iter() {
+            violations.extend(check_hole_rule(board, *rule, &self.config));
+        }
+        violations
+    }
+}
+
+/// Check a single hole rule against the drilled holes of a board or panel.
+pub fn check_hole_rule(
+    board: &BoardPanel,
+    rule: HoleRule,
+    config: &HoleCheckConfig,
+) -> Vec<HoleViolation> {
+    let scale = 1.0 / mm_per_unit(&board.header.units);
+    let tolerance = config.position_tolerance * scale;
+    let holes = &board.drilled_holes;
+
+    match rule {
+        HoleRule::OutsideOutline | HoleRule::InsideCutout | HoleRule::HoleToEdgeClearance => {
+            let outline = Polygon::from_outline(&board.outline.outline, tolerance.max(1e-4));
+            if outline.is_empty() {
+                return Vec::new();
+            }
+            let min_gap = config.min_hole_to_edge * scale;
+            holes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, hole)| {
+                    let in_outer = ring_contains(&outline.outer, hole.x, hole.y);
+                    let in_cutout = outline
+                        .holes
+                        .iter()
+                        .any(|c| ring_contains(c, hole.x, hole.y));
+                    let message = match rule {
+                        HoleRule::OutsideOutline if !in_outer => {
+                            format!("{} lies outside the outline.", describe(hole))
+                        }
+                        HoleRule::InsideCutout if in_outer && in_cutout => {
+                            format!("{} lies inside a cutout.", describe(hole))
+                        }
+                        HoleRule::HoleToEdgeClearance if in_outer && !in_cutout => {
+                            let gap =
+                                outline.distance_to_boundary(hole.x, hole.y) - hole.diameter / 2.0;
+                            if gap >= min_gap {
+                                return None;
+                            }
+                            format!(
+                                "{} is {} from the board edge, below the minimum of {}.",
+                                describe(hole),
+                                gap,
+                                min_gap
+                            )
+                        }
+                        _ => return None,
+                    };
+                    Some(violation(rule, vec![i], hole, message))
+                })
+                .collect()
+        }
+        HoleRule::Duplicate | HoleRule::Coincident | HoleRule::HoleToHoleClearance => {
+            let min_gap = config.min_hole_to_hole * scale;
+            let reach = match rule {
+                HoleRule::HoleToHoleClearance => min_gap,
+                _ => tolerance,
+            };
+            let mut violations = Vec::new();
+            for (i, j, distance) in nearby_pairs(holes, reach) {
+                let (a, b) = (&holes[i], &holes[j]);
+                let same_position = distance <= tolerance;
+                let same_diameter = (a.diameter - b.diameter).abs() <= tolerance;
+                let message = match rule {
+                    HoleRule::Duplicate if same_position && same_diameter => {
+                        format!("{} is duplicated by {}.", describe(a), describe(b))
+                    }
+                    HoleRule::Coincident if same_position && !same_diameter => {
+                        format!("{} is coincident with {}.", describe(a), describe(b))
+                    }
+                    HoleRule::HoleToHoleClearance if !same_position => {
+                        let gap = distance - (a.diameter + b.diameter) / 2.0;
+                        if gap >= min_gap {
+                            continue;
+                        }
+                        format!(
+                            "{} and {} are {} apart, below the minimum of {}.",
+                            describe(a),
+                            describe(b),
+                            gap,
+                            min_gap
+                        )
+                    }
+                    _ => continue,
+                };
+                violations.push(violation(rule, vec![i, j], a, message));
+            }
+            violations
+        }
+        HoleRule::ViaInKeepout => {
+            let keepouts: Vec<Polygon> = board
+                .via_keepouts
+                .iter()
+                .map(|k| Polygon::from_outline(&k.outline, tolerance.max(1e-4)))
+                .filter(|p| !p.is_empty())
+                .collect();
+            holes
+                .iter()
+                .enumerate()
+                .filter(|(_, hole)| hole.hole_type == "VIA")
+                .filter(|(_, hole)| {
+                    keepouts.iter().any(|k| {
+                        k.contains(hole.x, hole.y)
+                            || k.distance_to_boundary(hole.x, hole.y) < hole.diameter / 2.0
+                    })
+                })
+                .map(|(i, hole)| {
+                    let message = format!("{} overlaps a via keepout.", describe(hole));
+                    violation(rule, vec![i], hole, message)
+                })
+                .collect()
+        }
+        HoleRule::UnknownPinPart => {
+            // Some systems pad the reference designator at the end of a placement line.
+            let placed: HashSet<&str> = board
+                .component_placements
+                .iter()
+                .map(|p| p.reference_designator.trim_end())
+                .collect();
+            holes
+                .iter()
+                .enumerate()
+                .filter(|(_, hole)| {
+                    hole.hole_type == "PIN" && !placed.contains(hole.associated_part.as_str())
+                })
+                .map(|(i, hole)| {
+                    let message = format!(
+                        "{} belongs to {} which is not in the placement section.",
+                        describe(hole),
+                        hole.associated_part
+                    );
+                    violation(rule, vec![i], hole, message)
+                })
+                .collect()
+        }
+    }
+}
+
+fn violation(rule: HoleRule, holes: Vec<usize>, hole: &Hole, message: String) -> HoleViolation {
+    HoleViolation {
+        rule,
+        holes,
+        x: hole.x,
+        y: hole.y,
+        message,
+    }
+}
+
+fn describe(hole: &Hole) -> String {
+    format!(
+        "{} hole ({}) of diameter {} at ({}, {})",
+        hole.hole_type, hole.associated_part, hole.diameter, hole.x, hole.y
+    )
+}
+
+/// Find all pairs of holes whose edges are within `reach` of each other, returning the indices of
+/// both holes and the distance between their centres.
+fn nearby_pairs(holes: &[Hole], reach: f32) -> Vec<(usize, usize, f32)> {
+    let max_diameter = holes.iter().map(|h| h.diameter).fold(0.0, f32::max);
+    let mut order: Vec<usize> = (0..holes.len()).collect();
+    order.sort_by(|&a, &b| holes[a].x.total_cmp(&holes[b].x));
+
+    // Sweep along x, only comparing holes which could possibly be close enough.
+    let mut pairs = Vec::new();
+    for (n, &i) in order.iter().enumerate() {
+        for &j in order[n + 1..].iter() {
+            let (a, b) = (&holes[i], &holes[j]);
+            if b.x - a.x > reach + max_diameter {
+                break;
+            }
+            let distance = ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+            if distance - (a.diameter + b.diameter) / 2.0 < reach || distance <= reach {
+                pairs.push((i.min(j), i.max(j), distance));
+            }
+        }
+    }
+    pairs.sort_by_key(|&(i, j, _)| (i, j));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outlines::ViaKeepout;
+    use crate::parse_board_file;
+    use crate::point::Point;
+
+    fn hole(diameter: f32, x: f32, y: f32, part: &str, hole_type: &str) -> Hole {
+        Hole {
+            diameter,
+            x,
+            y,
+            plating_style: "PTH".to_string(),
+            associated_part: part.to_string(),
+            hole_type: hole_type.to_string(),
+            owner: "ECAD".to_string(),
+        }
+    }
+
+    fn rule_count(violations: &[HoleViolation], rule: HoleRule) -> usize {
+        violations.iter().filter(|v| v.rule == rule).count()
+    }
+
+    #[test]
+    fn test_sample_board_holes() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let violations = HoleRuleSet::default().check(&board);
+
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn test_outline_rules() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.drilled_holes = vec![
+            hole(30.0, 1000.0, 1000.0, "BOARD", "MTG"),
+            hole(30.0, 9000.0, 1000.0, "BOARD", "MTG"),
+            hole(30.0, 2700.0, 2350.0, "BOARD", "MTG"),
+            hole(30.0, 1000.0, 5480.0, "BOARD", "MTG"),
+        ];
+        let violations = HoleRuleSet::default().check(&board);
+
+        assert_eq!(rule_count(&violations, HoleRule::OutsideOutline), 1);
+        assert_eq!(rule_count(&violations, HoleRule::InsideCutout), 1);
+        assert_eq!(rule_count(&violations, HoleRule::HoleToEdgeClearance), 1);
+        let outside = violations
+            .iter()
+            .find(|v| v.rule == HoleRule::OutsideOutline)
+            .unwrap();
+        assert_eq!(outside.holes, vec![1]);
+    }
+
+    #[test]
+    fn test_hole_pair_rules() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.drilled_holes = vec![
+            hole(30.0, 1000.0, 1000.0, "BOARD", "VIA"),
+            hole(30.0, 1000.0, 1000.0, "BOARD", "VIA"),
+            hole(40.0, 2000.0, 1000.0, "BOARD", "VIA"),
+            hole(30.0, 2000.0, 1000.0, "BOARD", "VIA"),
+            hole(30.0, 3000.0, 1000.0, "BOARD", "VIA"),
+            hole(30.0, 3035.0, 1000.0, "BOARD", "VIA"),
+        ];
+        let violations = HoleRuleSet::default().check(&board);
+
+        assert_eq!(rule_count(&violations, HoleRule::Duplicate), 1);
+        assert_eq!(rule_count(&violations, HoleRule::Coincident), 1);
+        let clearance: Vec<_> = violations
+            .iter()
+            .filter(|v| v.rule == HoleRule::HoleToHoleClearance)
+            .collect();
+        assert_eq!(clearance.len(), 1);
+        assert_eq!(clearance[0].holes, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_via_keepout_and_pin_part() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        board.via_keepouts = vec![ViaKeepout {
+            owner: "ECAD".to_string(),
+            outline: vec![
+                Point {
+                    loop_label: 0,
+                    x: 1000.0,
+                    y: 1000.0,
+                    angle: 0.0,
+                },
+                Point {
+                    loop_label: 0,
+                    x: 1100.0,
+                    y: 1000.0,
+                    angle: 360.0,
+                },
+            ],
+        }];
+        board.drilled_holes = vec![
+            hole(20.0, 1050.0, 1000.0, "BOARD", "VIA"),
+            hole(20.0, 1105.0, 1000.0, "BOARD", "VIA"),
+            hole(20.0, 1500.0, 1000.0, "BOARD", "VIA"),
+            hole(30.0, 1050.0, 1000.0, "U99", "PIN"),
+            hole(30.0, 4000.0, 1000.0, "C1", "PIN"),
+            hole(30.0, 3000.0, 3500.0, "C2", "PIN"),
+        ];
+        board.component_placements[1].reference_designator = "C2    ".to_string();
+        let rules = HoleRuleSet {
+            rules: vec![HoleRule::ViaInKeepout, HoleRule::UnknownPinPart],
+            config: HoleCheckConfig::default(),
+        };
+        let violations = rules.check(&board);
+
+        let keepout: Vec<_> = violations
+            .iter()
+            .filter(|v| v.rule == HoleRule::ViaInKeepout)
+            .map(|v| v.holes[0])
+            .collect();
+        assert_eq!(keepout, vec![0, 1]);
+        let unknown: Vec<_> = violations
+            .iter()
+            .filter(|v| v.rule == HoleRule::UnknownPinPart)
+            .map(|v| v.holes[0])
+            .collect();
+        assert_eq!(unknown, vec![3]);
+    }
+}
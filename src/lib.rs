@@ -12,21 +12,95 @@
 //! let panel = parse_board_file("src/test_files/panel.emn").unwrap();
 //! let library = parse_library_file("src/test_files/library.emp").unwrap();
 //! ```
+//!
+//! A panel, its boards and their libraries can be loaded together as an
+//! [`Assembly`](assembly::Assembly), which resolves the references between them.
+//! ```
+//! use idf_parser::assembly::Assembly;
+//!
+//! let assembly = Assembly::from_files(
+//!     Some("src/test_files/panel.emn"),
+//!     &["src/test_files/board.emn"],
+//!     &["src/test_files/library.emp"],
+//! )
+//! .unwrap();
+//! ```
+//!
+//! # Serde
+//! With the `serde` feature enabled, [`BoardPanel`], [`Library`] and the header, outline, hole,
+//! note, placement and component types they are made of implement `Serialize` and
+//! `Deserialize`. Every struct becomes an object with its field names as keys, in declaration
+//! order, with lengths in the units of the file. Electrical properties are written sorted by
+//! name. A board serializes to JSON as:
+//! ```text
+//! {
+//!   "header": {"file_type": "BOARD_FILE", "version": 3, "system_id": "Sample File Generator",
+//!              "date": "10/22/96.16:02:44", "file_version": 1, "board_name": "sample_board", "units": "THOU"},
+//!   "outline": {"owner": "MCAD", "thickness": 62.0, "outline": [{"loop_label": 0, "x": 0.0, "y": 0.0, "angle": 0.0}]},
+//!   "other_outlines": [{"owner": "MCAD", "id": "...", "extrude_thickness": 0.0, "board_side": "TOP", "outline": []}],
+//!   "routing_outlines": [{"owner": "ECAD", "routing_layers": "ALL", "outline": []}],
+//!   "placement_outlines": [{"owner": "MCAD", "board_side": "TOP", "outline_height": 1000.0, "outline": []}],
+//!   "routing_keepouts": [{"owner": "ECAD", "routing_layers": "ALL", "outline": []}],
+//!   "via_keepouts": [{"owner": "ECAD", "outline": []}],
+//!   "placement_keepouts": [{"owner": "MCAD", "board_side": "BOTH", "keepout_height": 0.0, "outline": []}],
+//!   "placement_group_areas": [{"owner": "ECAD", "board_side": "TOP", "group_name": "...", "outline": []}],
+//!   "drilled_holes": [{"diameter": 30.0, "x": 1800.0, "y": 100.0, "plating_style": "PTH",
+//!                      "associated_part": "J1", "hole_type": "PIN", "owner": "ECAD"}],
+//!   "notes": [{"x": 3500.0, "y": 3300.0, "text_height": 75.0, "test_string_physical_length": 2500.0, "text": "..."}],
+//!   "component_placements": [{"package_name": "cs13_a", "part_number": "pn-cap", "reference_designator": "C1",
+//!                             "x": 4000.0, "y": 1000.0, "mounting_offset": 100.0, "rotation_angle": 0.0,
+//!                             "board_side": "TOP", "placement_status": "PLACED"}]
+//! }
+//! ```
+//!
+//! and a library as:
+//! ```text
+//! {
+//!   "header": {"version": 3, "system_id": "Sample File Generator", "date": "10/22/96.16:41:37", "file_version": 1},
+//!   "electrical_components": [{"geometry_name": "cs13_a", "part_number": "pn-cap", "units": "THOU", "height": 150.0,
+//!                              "outline": [], "properties": {"CAPACITANCE": 100.0, "TOLERANCE": 5.0}}],
+//!   "mechanical_components": [{"geometry_name": "...", "part_number": "...", "units": "MM", "height": 0.0, "outline": []}]
+//! }
+//! ```
+//!
+//! The `schema` feature adds the `schema` module, with JSON Schemas of both generated from the
+//! types.
 
 use crate::board::BoardPanel;
 use crate::library::Library;
 
+pub mod assembly;
 pub mod board;
+pub mod board_merge;
+pub mod changeset;
 pub mod component_placement;
 pub mod components;
+pub mod diff;
+pub mod discovery;
 pub mod drilled_holes;
+pub mod dxf;
+pub mod editing;
+pub mod flatten;
+pub mod geometry;
+pub mod gltf;
 pub mod headers;
+pub mod hole_checks;
 pub mod library;
+pub mod mesh;
 pub mod notes;
+pub mod openscad;
 pub mod outlines;
+pub mod panel_analysis;
+pub mod panelization;
 pub mod point;
 pub mod primitives;
-mod validation;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod solids;
+pub mod step;
+pub mod svg;
+pub mod validation;
+pub mod writer;
 
 /// Take in the path a board or panel .emn file and return a Board struct.
 pub fn parse_board_file(file_path: &str) -> Result<BoardPanel, String> {
@@ -57,38 +131,20 @@ pub fn parse_library_file(file_path: &str) -> Result<Library, String> {
     }
 }
 
-/// Parse an optional panel file, library file, and 1 or more board files and validate them.
-///
-/// An assembly is either a single board and a library file, or a panel file,
-/// 1 or more board files and a library file.
-/// Here we parse all the provided files and check that all board and component references are valid.
-fn parse_assembly(
-    panel_file: Option<&str>,
-    library_file: &str,
-    board_files: Vec<&str>,
-) -> Result<(Option<BoardPanel>, Library, Vec<BoardPanel>), String> {
-    let mut boards = Vec::new();
-
-    let panel = match panel_file {
-        Some(file) => Some(parse_board_file(file)?),
-        None => None,
-    };
-
-    let library = parse_library_file(library_file)?;
-
-    for board_file in board_files {
-        boards.push(parse_board_file(board_file)?);
-    }
-
-    for board in &boards {
-        validation::library_references_valid(&library, board)?;
+/// Write a board or panel struct to a .emn file at the given path.
+pub fn write_board_file(file_path: &str, board: &BoardPanel) -> Result<(), String> {
+    if !file_path.ends_with(".emn") {
+        return Err("Board and panel files must end with .emn.".to_string());
     }
+    std::fs::write(file_path, writer::write_board_or_panel(board)).map_err(|e| e.to_string())
+}
 
-    if let Some(panel) = &panel {
-        validation::panel_references_valid(panel, &boards)?;
+/// Write a library struct to a .emp file at the given path.
+pub fn write_library_file(file_path: &str, library: &Library) -> Result<(), String> {
+    if !file_path.ends_with(".emp") {
+        return Err("Library files must end with .emp.".to_string());
     }
-
-    Ok((panel, library, boards))
+    std::fs::write(file_path, writer::write_library(library)).map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
@@ -143,19 +199,19 @@ mod tests {
         parse_library_file("src/test_files/esp.emp").unwrap();
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_parse_assembly() {
-        let panel = Some("src/test_files/panel.emn");
-        let library = "src/test_files/library.emp";
-        let boards = vec!["src/test_files/board.emn"];
-
-        let result = parse_assembly(panel, library, boards.clone());
-        assert!(result.is_ok());
-
-        // This panel file references a board that doesn't exist
-        let invalid_panel = Some("src/test_files/invalid_panel.emn");
-
-        let result = parse_assembly(invalid_panel, library, boards);
-        assert!(result.is_err());
+    fn test_serde_round_trip() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<BoardPanel>(&json).unwrap(), board);
+        assert!(json.contains(
+            r#"{"package_name":"cs13_a","part_number":"pn-cap","reference_designator":"C1","x":4000.0,"y":1000.0,"mounting_offset":100.0,"rotation_angle":0.0,"board_side":"TOP","placement_status":"PLACED"}"#
+        ));
+
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let json = serde_json::to_string(&library).unwrap();
+        assert_eq!(serde_json::from_str::<Library>(&json).unwrap(), library);
+        assert!(json.contains(r#""properties":{"CAPACITANCE":100.0,"TOLERANCE":5.0}"#));
     }
 }
@@ -1,15 +1,192 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
 use crate::components::{
-    ElectricalComponent, MechanicalComponent, electrical_component, mechanical_component,
+    Component, ElectricalComponent, MechanicalComponent, electrical_component, mechanical_component,
 };
+use crate::geometry::mm_per_unit;
 use crate::headers::{LibraryHeader, parse_library_header};
 use nom::Parser;
 use nom::multi::many0;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 
 #[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Library {
     pub header: LibraryHeader,
     pub electrical_components: Vec<ElectricalComponent>,
     pub mechanical_components: Vec<MechanicalComponent>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    lookup: GeometryLookup,
+}
+
+/// The position in [Library::components] of the first component with each geometry name, built
+/// on the first call to [Library::get].
+///
+/// The numbers of components it was built from are kept alongside, so that it is rebuilt after
+/// components are added or removed. It takes no part in comparing or cloning libraries.
+#[derive(Default)]
+struct GeometryLookup(Mutex<Option<GeometryPositions>>);
+
+struct GeometryPositions {
+    /// The number of electrical and mechanical components.
+    counts: (usize, usize),
+    by_geometry: HashMap<String, usize>,
+}
+
+impl std::fmt::Debug for GeometryLookup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("GeometryLookup")
+    }
+}
+
+impl PartialEq for GeometryLookup {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Clone for GeometryLookup {
+    fn clone(&self) -> Self {
+        GeometryLookup::default()
+    }
+}
+
+impl Library {
+    /// Every component of the library, electrical components first.
+    pub fn components(&self) -> impl Iterator<Item = Component<'_>> + Clone {
+        self.electrical_components
+            .iter()
+            .map(Component::Electrical)
+            .chain(self.mechanical_components.iter().map(Component::Mechanical))
+    }
+
+    /// Find the first component with the given geometry name.
+    ///
+    /// Lookups go through an index built on first use, which is rebuilt when components have
+    /// been added or removed, or when the component found no longer has the geometry name. A
+    /// component renamed in place to a name not in the index is not found until then. Use
+    /// [Library::index] to look up every component of a geometry, or by part number.
+    ///
+    /// # Example
+    /// ```
+    /// use idf_parser::parse_library_file;
+    ///
+    /// let library = parse_library_file("src/test_files/library.emp").unwrap();
+    /// assert_eq!(library.get("cs13_a").unwrap().part_number(), "pn-cap");
+    /// assert!(library.get("missing").is_none());
+    /// ```
+    pub fn get(&self, geometry_name: &str) -> Option<Component<'_>> {
+        let counts = (
+            self.electrical_components.len(),
+            self.mechanical_components.len(),
+        );
+        let mut lookup = self.lookup.0.lock().unwrap_or_else(|e| e.into_inner());
+        let stale = lookup.as_ref().is_none_or(|positions| {
+            positions.counts != counts
+                || positions
+                    .by_geometry
+                    .get(geometry_name)
+                    .is_some_and(|&i| self.component_at(i).geometry_name() != geometry_name)
+        });
+        if stale {
+            let mut by_geometry = HashMap::new();
+            for (i, component) in self.components().enumerate() {
+                by_geometry
+                    .entry(component.geometry_name().to_string())
+                    .or_insert(i);
+            }
+            *lookup = Some(GeometryPositions {
+                counts,
+                by_geometry,
+            });
+        }
+        let positions = lookup.as_ref()?;
+        positions
+            .by_geometry
+            .get(geometry_name)
+            .map(|&i| self.component_at(i))
+    }
+
+    /// The component at a position of [Library::components].
+    fn component_at(&self, i: usize) -> Component<'_> {
+        match self.electrical_components.get(i) {
+            Some(c) => Component::Electrical(c),
+            None => Component::Mechanical(
+                &self.mechanical_components[i - self.electrical_components.len()],
+            ),
+        }
+    }
+
+    /// Build an index of the components by geometry name.
+    pub fn index(&self) -> LibraryIndex<'_> {
+        let mut by_geometry: HashMap<&str, Vec<Component>> = HashMap::new();
+        for component in self.components() {
+            by_geometry
+                .entry(component.geometry_name())
+                .or_default()
+                .push(component);
+        }
+        LibraryIndex { by_geometry }
+    }
+}
+
+/// Components of a library indexed by geometry name, see [`Library::index`].
+///
+/// # Example
+/// ```
+/// use idf_parser::parse_library_file;
+///
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let index = library.index();
+/// let capacitor = index.get("cs13_a").unwrap();
+/// assert_eq!(capacitor.part_number(), "pn-cap");
+/// assert_eq!(capacitor.properties().unwrap()["CAPACITANCE"], 100.0);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LibraryIndex<'a> {
+    by_geometry: HashMap<&'a str, Vec<Component<'a>>>,
+}
+
+impl<'a> LibraryIndex<'a> {
+    /// The first component with the given geometry name.
+    pub fn get(&self, geometry_name: &str) -> Option<Component<'a>> {
+        self.get_all(geometry_name).first().copied()
+    }
+
+    /// Every component with the given geometry name, in library order. A geometry may be defined
+    /// several times with different part numbers.
+    pub fn get_all(&self, geometry_name: &str) -> &[Component<'a>] {
+        self.by_geometry
+            .get(geometry_name)
+            .map_or(&[], |components| components.as_slice())
+    }
+
+    /// The component with the given geometry name and part number.
+    pub fn get_part(&self, geometry_name: &str, part_number: &str) -> Option<Component<'a>> {
+        self.get_all(geometry_name)
+            .iter()
+            .find(|c| c.part_number() == part_number)
+            .copied()
+    }
+
+    /// The component placed by a placement: the one matching its package name and part number,
+    /// or else the first with its package name.
+    pub fn for_placement(&self, placement: &ComponentPlacement) -> Option<Component<'a>> {
+        self.get_part(&placement.package_name, &placement.part_number)
+            .or_else(|| self.get(&placement.package_name))
+    }
+
+    /// Number of distinct geometry names.
+    pub fn len(&self) -> usize {
+        self.by_geometry.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_geometry.is_empty()
+    }
 }
 
 /// Parses a library emp file which contains detail on electrical and mechanical components.
@@ -39,6 +216,7 @@ pub fn parse_library(input: &str) -> Result<Library, nom::Err<nom::error::Error<
         header,
         electrical_components,
         mechanical_components,
+        ..Default::default()
     };
 
     // Check nothing is remaining
@@ -52,11 +230,225 @@ pub fn parse_library(input: &str) -> Result<Library, nom::Err<nom::error::Error<
     }
 }
 
+/// How to resolve components which are defined differently in more than one library.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ConflictPolicy {
+    /// Keep the definition from the earliest library.
+    FirstWins,
+    /// Keep the definition from the latest library.
+    LastWins,
+    /// Fail the merge.
+    Error,
+}
+
+/// Two libraries defining the same component differently.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct LibraryConflict {
+    pub geometry_name: String,
+    pub part_number: String,
+    /// Index of the library with the definition seen first, and of the conflicting library.
+    pub libraries: (usize, usize),
+    /// Description of how the definitions differ.
+    pub reason: String,
+}
+
+/// The duplicate components found while merging libraries.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct MergeReport {
+    /// Geometry name and part number of each duplicate which matched the definition already
+    /// merged and was dropped.
+    pub identical: Vec<(String, String)>,
+    /// Duplicates which differ from the definition already merged.
+    pub conflicts: Vec<LibraryConflict>,
+}
+
+/// Describe how two definitions of a component differ, returning None if they are the same.
+///
+/// Lengths are converted to millimetres before being compared, so the same component defined in
+/// THOU and MM is identical. `tolerance` is in millimetres.
+fn component_difference(a: Component, b: Component, tolerance: f32) -> Option<String> {
+    let (scale_a, scale_b) = (mm_per_unit(a.units()), mm_per_unit(b.units()));
+    let close = |x: f32, y: f32| (x * scale_a - y * scale_b).abs() <= tolerance;
+
+    match (a, b) {
+        (Component::Electrical(_), Component::Mechanical(_))
+        | (Component::Mechanical(_), Component::Electrical(_)) => {
+            return Some("defined as both electrical and mechanical".to_string());
+        }
+        _ => {}
+    }
+    if !close(a.height(), b.height()) {
+        return Some(format!(
+            "height {} {} differs from {} {}",
+            b.height(),
+            b.units(),
+            a.height(),
+            a.units()
+        ));
+    }
+    let same_outline = a.outline().len() == b.outline().len()
+        && a.outline().iter().zip(b.outline()).all(|(p, q)| {
+            p.loop_label == q.loop_label
+                && close(p.x, q.x)
+                && close(p.y, q.y)
+                && (p.angle - q.angle).abs() <= 1e-3
+        });
+    if !same_outline {
+        return Some("outline differs".to_string());
+    }
+    if let (Component::Electrical(a), Component::Electrical(b)) = (a, b)
+        && a.properties != b.properties
+    {
+        return Some("electrical properties differ".to_string());
+    }
+    None
+}
+
+/// Merge several libraries into one, dropping duplicate components.
+///
+/// Components are identified by their geometry name and part number. A duplicate whose height
+/// and outline match the first definition to within `tolerance` millimetres, after converting
+/// both to the same units, is dropped. Any other duplicate is a conflict, resolved according to
+/// `policy`. The merged library keeps the header of the first library, and each component
+/// keeps its own units.
+///
+/// # Example
+/// ```
+/// use idf_parser::library::{ConflictPolicy, merge_libraries};
+/// use idf_parser::parse_library_file;
+///
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let (merged, report) =
+///     merge_libraries(&[library.clone(), library.clone()], ConflictPolicy::Error, 0.001).unwrap();
+/// assert_eq!(merged, library);
+/// assert!(report.conflicts.is_empty());
+/// ```
+pub fn merge_libraries(
+    libraries: &[Library],
+    policy: ConflictPolicy,
+    tolerance: f32,
+) -> Result<(Library, MergeReport), String> {
+    let Some(first) = libraries.first() else {
+        return Err("At least one library is required to merge.".to_string());
+    };
+
+    // The merged components in order of first appearance, with the library they came from.
+    let mut merged: Vec<(usize, Component)> = Vec::new();
+    let mut positions: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut report = MergeReport::default();
+
+    for (index, library) in libraries.iter().enumerate() {
+        for component in library.components() {
+            let key = (component.geometry_name(), component.part_number());
+            let Some(&position) = positions.get(&key) else {
+                positions.insert(key, merged.len());
+                merged.push((index, component));
+                continue;
+            };
+            let (existing_index, existing) = merged[position];
+            let Some(reason) = component_difference(existing, component, tolerance) else {
+                report
+                    .identical
+                    .push((key.0.to_string(), key.1.to_string()));
+                continue;
+            };
+            let conflict = LibraryConflict {
+                geometry_name: key.0.to_string(),
+                part_number: key.1.to_string(),
+                libraries: (existing_index, index),
+                reason,
+            };
+            match policy {
+                ConflictPolicy::FirstWins => {}
+                ConflictPolicy::LastWins => merged[position] = (index, component),
+                ConflictPolicy::Error => {
+                    return Err(format!(
+                        "Component {} ({}) in library {} conflicts with library {}: {}.",
+                        conflict.geometry_name,
+                        conflict.part_number,
+                        index,
+                        existing_index,
+                        conflict.reason
+                    ));
+                }
+            }
+            report.conflicts.push(conflict);
+        }
+    }
+
+    let mut library = Library {
+        header: first.header.clone(),
+        ..Default::default()
+    };
+    for (_, component) in merged {
+        match component {
+            Component::Electrical(c) => library.electrical_components.push(c.clone()),
+            Component::Mechanical(c) => library.mechanical_components.push(c.clone()),
+        }
+    }
+    Ok((library, report))
+}
+
+/// Create a library containing only the components placed on the given boards.
+///
+/// A component is kept if a placement refers to both its geometry name and part number. When a
+/// placed geometry has no component with a matching part number, every component with that
+/// geometry name is kept, as the placement still resolves to it by geometry name. The header is
+/// copied from `library`.
+///
+/// # Example
+/// ```
+/// use idf_parser::library::subset_library;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let subset = subset_library(&library, &[board]);
+/// assert!(subset.electrical_components.len() <= library.electrical_components.len());
+/// ```
+pub fn subset_library(library: &Library, boards: &[BoardPanel]) -> Library {
+    let placed: HashSet<(&str, &str)> = boards
+        .iter()
+        .flat_map(|b| b.component_placements.iter())
+        .filter(|p| p.reference_designator != "BOARD")
+        .map(|p| (p.package_name.as_str(), p.part_number.as_str()))
+        .collect();
+    let placed_geometries: HashSet<&str> = placed.iter().map(|&(geometry, _)| geometry).collect();
+
+    let exact_geometries: HashSet<&str> = library
+        .components()
+        .filter(|c| placed.contains(&(c.geometry_name(), c.part_number())))
+        .map(|c| c.geometry_name())
+        .collect();
+    let keep = |c: Component| {
+        placed.contains(&(c.geometry_name(), c.part_number()))
+            || (placed_geometries.contains(c.geometry_name())
+                && !exact_geometries.contains(c.geometry_name()))
+    };
+
+    Library {
+        header: library.header.clone(),
+        electrical_components: library
+            .electrical_components
+            .iter()
+            .filter(|c| keep(Component::Electrical(c)))
+            .cloned()
+            .collect(),
+        mechanical_components: library
+            .mechanical_components
+            .iter()
+            .filter(|c| keep(Component::Mechanical(c)))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::components::ComponentKind;
     use crate::point::Point;
-    use std::collections::HashMap;
     #[test]
     fn test_library() {
         let input = ".HEADER
@@ -195,9 +587,140 @@ plcc_20 pn-pal16l8-plcc THOU 14.0
         let expected_library = Library {
             header,
             electrical_components,
-            mechanical_components: vec![],
+            ..Default::default()
         };
 
         assert_eq!(library, expected_library);
     }
+
+    #[test]
+    fn test_merge_identical_across_units() {
+        let library = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        let mut metric = library.clone();
+        for component in metric.electrical_components.iter_mut() {
+            component.units = "MM".to_string();
+            component.height *= 0.0254;
+            for point in component.outline.iter_mut() {
+                point.x *= 0.0254;
+                point.y *= 0.0254;
+            }
+        }
+        let (merged, report) =
+            merge_libraries(&[library.clone(), metric], ConflictPolicy::Error, 0.001).unwrap();
+        assert_eq!(merged, library);
+        assert_eq!(report.identical.len(), library.electrical_components.len());
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_conflict_policies() {
+        let library = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        let mut taller = library.clone();
+        taller.electrical_components[0].height = 200.0;
+        taller.electrical_components.push(ElectricalComponent {
+            geometry_name: "new".to_string(),
+            ..Default::default()
+        });
+        let libraries = [library.clone(), taller];
+
+        let (first, report) =
+            merge_libraries(&libraries, ConflictPolicy::FirstWins, 0.001).unwrap();
+        assert_eq!(first.electrical_components[0].height, 150.0);
+        assert_eq!(
+            first.electrical_components.len(),
+            library.electrical_components.len() + 1
+        );
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].geometry_name, "cs13_a");
+        assert_eq!(report.conflicts[0].libraries, (0, 1));
+
+        let (last, _) = merge_libraries(&libraries, ConflictPolicy::LastWins, 0.001).unwrap();
+        assert_eq!(last.electrical_components[0].height, 200.0);
+
+        assert!(merge_libraries(&libraries, ConflictPolicy::Error, 0.001).is_err());
+        assert!(merge_libraries(&[], ConflictPolicy::Error, 0.001).is_err());
+    }
+
+    #[test]
+    fn test_subset_library() {
+        let board = crate::parse_board_file("src/test_files/board.emn").unwrap();
+        let mut library = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        library.electrical_components.push(ElectricalComponent {
+            geometry_name: "unused".to_string(),
+            ..Default::default()
+        });
+        // Same geometry as a placed component but a part number which is never placed.
+        library.electrical_components.push(ElectricalComponent {
+            geometry_name: "cs13_a".to_string(),
+            part_number: "pn-other".to_string(),
+            ..Default::default()
+        });
+
+        let subset = subset_library(&library, std::slice::from_ref(&board));
+        assert_eq!(subset.header, library.header);
+        let names: Vec<(&str, &str)> = subset
+            .electrical_components
+            .iter()
+            .map(|c| (c.geometry_name.as_str(), c.part_number.as_str()))
+            .collect();
+        assert!(names.contains(&("cs13_a", "pn-cap")));
+        assert!(!names.contains(&("cs13_a", "pn-other")));
+        assert!(!names.iter().any(|&(geometry, _)| geometry == "unused"));
+        for placement in board.component_placements.iter() {
+            assert!(
+                names
+                    .iter()
+                    .any(|&(geometry, _)| geometry == placement.package_name)
+            );
+        }
+    }
+
+    #[test]
+    fn test_components_and_index() {
+        let mut library = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        library.mechanical_components.push(MechanicalComponent {
+            geometry_name: "cs13_a".to_string(),
+            part_number: "pn-bracket".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(library.components().count(), 6);
+
+        let index = library.index();
+        assert_eq!(index.get("cc1210").unwrap().height(), 67.0);
+        assert!(index.get("missing").is_none());
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.get_all("cs13_a").len(), 2);
+        assert_eq!(
+            index.get("cs13_a").unwrap().kind(),
+            ComponentKind::Electrical
+        );
+        let bracket = index.get_part("cs13_a", "pn-bracket").unwrap();
+        assert!(bracket.properties().is_none());
+        assert!(bracket.as_mechanical().is_some());
+        assert!(index.get_all("missing").is_empty());
+    }
+
+    #[test]
+    fn test_get_follows_changes() {
+        let mut library = crate::parse_library_file("src/test_files/library.emp").unwrap();
+        assert_eq!(library.get("cc1210").unwrap().height(), 67.0);
+        assert!(library.get("bracket").is_none());
+
+        // Adding a component rebuilds the index.
+        library.mechanical_components.push(MechanicalComponent {
+            geometry_name: "bracket".to_string(),
+            ..Default::default()
+        });
+        assert!(library.get("bracket").unwrap().as_mechanical().is_some());
+
+        // So does finding a component which has since been renamed.
+        library.electrical_components[0].geometry_name = "renamed".to_string();
+        assert!(library.get("cs13_a").is_none());
+        assert!(library.get("renamed").is_some());
+
+        // Clones and comparisons are unaffected by the index.
+        let copy = library.clone();
+        assert_eq!(copy, library);
+        assert_eq!(copy.get("renamed"), library.get("renamed"));
+    }
 }
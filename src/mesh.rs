@@ -0,0 +1,121 @@
+use crate::board::BoardPanel;
+use crate::library::Library;
+use crate::solids::{Extrusion, board_extrusions};
+
+/// The extruded solids worth meshing, leaving out those with no outline or no height.
+fn solids(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> Vec<Extrusion> {
+    board_extrusions(board, library, tolerance)
+        .into_iter()
+        .filter(|s| !s.polygon.is_empty() && s.top > s.bottom)
+        .collect()
+}
+
+/// Replace the whitespace in a name, which would end it early in STL and OBJ files.
+fn name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Write the board and its placed components as a single ASCII STL solid.
+///
+/// The solids are those of [board_extrusions](crate::solids::board_extrusions), in millimetres
+/// with arcs replaced by facets deviating from them by no more than `tolerance` millimetres.
+///
+/// # Example
+/// ```
+/// use idf_parser::mesh::write_stl;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let stl = write_stl(&board, Some(&library), 0.05);
+/// assert!(stl.starts_with("solid sample_board\n"));
+/// ```
+pub fn write_stl(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> String {
+    let solid_name = name(&board.header.board_name);
+    let mut output = format!("solid {}\n", solid_name);
+    for solid in solids(board, library, tolerance) {
+        let mesh = solid.mesh();
+        for triangle in &mesh.triangles {
+            let [nx, ny, nz] = mesh.normal(triangle);
+            output.push_str(&format!("facet normal {} {} {}\n outer loop\n", nx, ny, nz));
+            for &i in triangle {
+                let [x, y, z] = mesh.vertices[i];
+                output.push_str(&format!("  vertex {} {} {}\n", x, y, z));
+            }
+            output.push_str(" endloop\nendfacet\n");
+        }
+    }
+    output.push_str(&format!("endsolid {}\n", solid_name));
+    output
+}
+
+/// Write the board and its placed components as a Wavefront OBJ file.
+///
+/// Each solid is its own object and group, named after the board or the component's reference
+/// designator, so viewers can show, hide and pick components individually. The solids are those
+/// of [board_extrusions](crate::solids::board_extrusions), in millimetres with arcs replaced by
+/// facets deviating from them by no more than `tolerance` millimetres.
+///
+/// # Example
+/// ```
+/// use idf_parser::mesh::write_obj;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let obj = write_obj(&board, Some(&library), 0.05);
+/// assert!(obj.contains("\no C1\ng C1\n"));
+/// ```
+pub fn write_obj(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> String {
+    let mut output = format!("# {}\n", board.header.board_name);
+    // OBJ vertex indices start at 1 and run across the whole file.
+    let mut offset = 1;
+    for solid in solids(board, library, tolerance) {
+        let mesh = solid.mesh();
+        let object = name(&solid.name);
+        output.push_str(&format!("o {}\ng {}\n", object, object));
+        for [x, y, z] in &mesh.vertices {
+            output.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+        for [a, b, c] in &mesh.triangles {
+            output.push_str(&format!("f {} {} {}\n", a + offset, b + offset, c + offset));
+        }
+        offset += mesh.vertices.len();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_stl_and_obj() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let meshes: Vec<_> = solids(&board, Some(&library), 0.05)
+            .iter()
+            .map(|s| s.mesh())
+            .collect();
+        let triangles: usize = meshes.iter().map(|m| m.triangles.len()).sum();
+        let vertices: usize = meshes.iter().map(|m| m.vertices.len()).sum();
+
+        let stl = write_stl(&board, Some(&library), 0.05);
+        assert_eq!(stl.matches("facet normal").count(), triangles);
+        assert_eq!(stl.matches("  vertex ").count(), 3 * triangles);
+        assert!(stl.ends_with("endsolid sample_board\n"));
+
+        let obj = write_obj(&board, Some(&library), 0.05);
+        let lines = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(lines("o "), meshes.len());
+        assert_eq!(lines("v "), vertices);
+        assert_eq!(lines("f "), triangles);
+        let highest = obj
+            .lines()
+            .filter_map(|l| l.strip_prefix("f "))
+            .flat_map(|l| l.split(' ').map(|i| i.parse::<usize>().unwrap()))
+            .max();
+        assert_eq!(highest, Some(vertices));
+    }
+}
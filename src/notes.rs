@@ -16,6 +16,8 @@ use nom::sequence::delimited;
 /// rigorous translations of text such as for transferring manufacturing drawings. The association of a
 /// note to its subject is inferred by its location.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Note {
     pub x: f32,
     pub y: f32,
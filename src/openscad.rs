@@ -0,0 +1,268 @@
+use crate::board::BoardPanel;
+use crate::geometry::{Polygon, mm_per_unit};
+use crate::library::Library;
+use crate::point::Point;
+use crate::solids::board_extrusions;
+use std::collections::BTreeMap;
+
+/// Turn a name into an OpenSCAD identifier, prefixed so that it never starts with a digit.
+fn identifier(prefix: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", prefix, name)
+}
+
+/// An OpenSCAD `polygon` of all the rings of a polygon, the holes cut out of the outer ring.
+fn polygon(polygon: &Polygon) -> String {
+    let mut points = Vec::new();
+    let mut paths = Vec::new();
+    for ring in polygon.rings() {
+        let path: Vec<String> = (points.len()..points.len() + ring.len())
+            .map(|i| i.to_string())
+            .collect();
+        paths.push(format!("[{}]", path.join(",")));
+        points.extend(ring.iter().map(|(x, y)| format!("[{},{}]", x, y)));
+    }
+    format!(
+        "polygon(points=[{}], paths=[{}]);",
+        points.join(","),
+        paths.join(",")
+    )
+}
+
+/// Write the board and its placed components as an OpenSCAD model in millimetres.
+///
+/// The model is made up of modules which can be reused from other files with `use`:
+/// - `board()`: the board outline and cutouts extruded to the board thickness, from z = 0.
+/// - `mounting_holes(extra = 0)`: a cylinder through the board for each MTG drilled hole,
+///   lengthened by `extra` above and below.
+/// - `package_<geometry>_<part number>()`: the envelope of a library component, its outline
+///   extruded to its height from z = 0 in the component's own coordinates.
+/// - `refdes_<reference designator>()`: a package translated, rotated and, on the bottom side,
+///   mirrored into place from its component placement, sitting on or hanging below the board.
+/// - `components()`: every component found in the library, leaving out UNPLACED placements.
+///
+/// Names are made into identifiers by replacing any character which is not a letter or digit
+/// with `_`, and the index of the placement is appended to a module name already taken. Arcs
+/// are replaced by line segments deviating from them by no more than `tolerance` millimetres.
+/// The top level renders the board with its mounting holes drilled through, and the
+/// components.
+///
+/// # Example
+/// ```
+/// use idf_parser::openscad::write_openscad;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let scad = write_openscad(&board, Some(&library), 0.05);
+/// assert!(scad.contains("module refdes_C1() {"));
+/// ```
+pub fn write_openscad(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> String {
+    let board_scale = mm_per_unit(&board.header.units);
+    let thickness = board.outline.thickness * board_scale;
+    let mut output = format!("// {}\n\n", board.header.board_name);
+
+    let outline = board_extrusions(board, None, tolerance).remove(0).polygon;
+    output.push_str(&format!(
+        "module board() {{\n    linear_extrude(height={}) {}\n}}\n\n",
+        thickness,
+        polygon(&outline)
+    ));
+
+    output.push_str("module mounting_holes(extra = 0) {\n");
+    for hole in board.drilled_holes.iter().filter(|h| h.hole_type == "MTG") {
+        output.push_str(&format!(
+            "    translate([{},{},-extra]) cylinder(d={}, h={} + 2 * extra, $fn=32);\n",
+            hole.x * board_scale,
+            hole.y * board_scale,
+            hole.diameter * board_scale,
+            thickness
+        ));
+    }
+    output.push_str("}\n");
+
+    let index = library.map(|l| l.index());
+    // The module of each distinct geometry name and part number, and the modules by name.
+    let mut modules: BTreeMap<(&str, &str), String> = BTreeMap::new();
+    let mut packages = BTreeMap::new();
+    let mut placed = Vec::new();
+    for (i, placement) in board.component_placements.iter().enumerate() {
+        if placement.placement_status == "UNPLACED" {
+            continue;
+        }
+        let Some(component) = index.as_ref().and_then(|i| i.for_placement(placement)) else {
+            continue;
+        };
+        let key = (component.geometry_name(), component.part_number());
+        let package = modules.entry(key).or_insert_with(|| {
+            let mut package = identifier("package", &format!("{}_{}", key.0, key.1));
+            // Part numbers such as pn-cap and pn_cap make the same identifier.
+            if packages.contains_key(&package) {
+                package = format!("{}_{}", package, i);
+            }
+            package
+        });
+        let package = package.clone();
+        packages.entry(package.clone()).or_insert_with(|| {
+            let scale = mm_per_unit(component.units());
+            let points: Vec<Point> = component
+                .outline()
+                .iter()
+                .map(|p| Point {
+                    x: p.x * scale,
+                    y: p.y * scale,
+                    ..p.clone()
+                })
+                .collect();
+            (
+                component.height() * scale,
+                Polygon::from_outline(&points, tolerance),
+            )
+        });
+        let height = packages[&package].0;
+        let (offset, bottom) = (
+            placement.mounting_offset * board_scale,
+            placement.board_side == "BOTTOM",
+        );
+        let (z, mirror) = if bottom {
+            (-offset - height, "mirror([1,0,0]) ")
+        } else {
+            (thickness + offset, "")
+        };
+        let mut module = identifier("refdes", &placement.reference_designator);
+        // NOREFDES placements, amongst others, share a reference designator.
+        if placed.contains(&module) {
+            module = format!("{}_{}", module, i);
+        }
+        output.push_str(&format!(
+            "\n// {} {} {}\nmodule {}() {{\n    translate([{},{},{}]) rotate([0,0,{}]) {}{}();\n}}\n",
+            placement.reference_designator,
+            placement.part_number,
+            placement.board_side,
+            module,
+            placement.x * board_scale,
+            placement.y * board_scale,
+            z,
+            placement.rotation_angle,
+            mirror,
+            package
+        ));
+        placed.push(module);
+    }
+
+    for (package, (height, outline)) in &packages {
+        output.push_str(&format!(
+            "\nmodule {}() {{\n    linear_extrude(height={}) {}\n}}\n",
+            package,
+            height,
+            polygon(outline)
+        ));
+    }
+
+    output.push_str("\nmodule components() {\n");
+    for module in &placed {
+        output.push_str(&format!("    {}();\n", module));
+    }
+    output.push_str(
+        "}\n\ndifference() {\n    board();\n    mounting_holes(0.01);\n}\ncomponents();\n",
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_write_openscad() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let scad = write_openscad(&board, Some(&library), 0.05);
+
+        // The board has one cutout, so its polygon has two paths.
+        let board_module = scad.split("module board()").nth(1).unwrap();
+        let board_module = board_module.split("\n}").next().unwrap();
+        assert!(board_module.contains("linear_extrude(height=1.5748) polygon("));
+        assert_eq!(
+            board_module
+                .split("paths=[")
+                .nth(1)
+                .unwrap()
+                .matches('[')
+                .count(),
+            2
+        );
+
+        let mtg = board
+            .drilled_holes
+            .iter()
+            .filter(|h| h.hole_type == "MTG")
+            .count();
+        assert_eq!(scad.matches("cylinder(").count(), mtg);
+
+        assert!(scad.contains(
+            "module refdes_C1() {\n    translate([101.6,25.4,4.1148]) rotate([0,0,0]) package_cs13_a_pn_cap();\n}"
+        ));
+        assert!(scad.contains("mirror([1,0,0]) package_"));
+        assert!(scad.contains(
+            "module package_cs13_a_pn_cap() {\n    linear_extrude(height=3.81) polygon("
+        ));
+        let placed = scad.split("module components() {\n").nth(1).unwrap();
+        assert_eq!(
+            placed.split('}').next().unwrap().lines().count(),
+            board.component_placements.len()
+        );
+    }
+
+    #[test]
+    fn test_colliding_identifiers() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let mut library = parse_library_file("src/test_files/library.emp").unwrap();
+        let mut other = library.electrical_components[0].clone();
+        assert_eq!(other.part_number, "pn-cap");
+        other.part_number = "pn_cap".to_string();
+        library.electrical_components.push(other);
+        // C1 is placed as pn_cap, and a copy of it as pn-cap after every other placement.
+        let c1 = board
+            .component_placements
+            .iter()
+            .position(|p| p.reference_designator == "C1")
+            .unwrap();
+        let mut c9 = board.component_placements[c1].clone();
+        c9.reference_designator = "C9".to_string();
+        board.component_placements.push(c9);
+        board.component_placements[c1].part_number = "pn_cap".to_string();
+        let scad = write_openscad(&board, Some(&library), 0.05);
+
+        let suffixed = format!(
+            "package_cs13_a_pn_cap_{}",
+            board.component_placements.len() - 1
+        );
+        assert_eq!(scad.matches("module package_cs13_a_pn_cap() {").count(), 1);
+        assert_eq!(
+            scad.matches(&format!("module {}() {{", suffixed)).count(),
+            1
+        );
+        assert!(scad.contains(
+            "module refdes_C1() {\n    translate([101.6,25.4,4.1148]) rotate([0,0,0]) package_cs13_a_pn_cap();"
+        ));
+        assert!(scad.contains(&format!(
+            "module refdes_C9() {{\n    translate([101.6,25.4,4.1148]) rotate([0,0,0]) {}();",
+            suffixed
+        )));
+    }
+
+    #[test]
+    fn test_unplaced_components_are_left_out() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        board.component_placements[0].placement_status = "UNPLACED".to_string();
+        let scad = write_openscad(&board, Some(&library), 0.05);
+        assert!(!scad.contains("refdes_C1"));
+        assert!(scad.contains("refdes_C2();"));
+    }
+}
@@ -18,6 +18,8 @@ use nom::number::complete::float;
 /// thickness. The outline and cutouts consist of simple closed curves made up of arcs and lines.
 /// Only one outline may be specified, but multiple cutouts are allowed.
 #[derive(Clone, Debug, PartialEq, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BoardPanelOutline {
     pub owner: String, // MCAD, ECAD or UNOWNED
     pub thickness: f32,
@@ -52,6 +54,8 @@ pub fn parse_board_panel_outline(input: &str) -> IResult<&str, BoardPanelOutline
 /// the board outline such as for defining a heatsink or board core. The outline and cutouts consist of
 /// simple closed curves made up of arcs and lines. Multiple other outline sections may be specified.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OtherOutline {
     pub owner: String, // MCAD, ECAD or UNOWNED
     pub id: String,
@@ -93,6 +97,8 @@ pub fn parse_other_outline(input: &str) -> IResult<&str, OtherOutline> {
 /// arcs and lines. Portions of routing outlines on a panel that lie on a board in the panel are inherited
 /// by that board. Multiple routing outlines may be defined.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RoutingOutline {
     pub owner: String,          // MCAD, ECAD or UNOWNED
     pub routing_layers: String, // TOP, BOTTOM, BOTH, INNER or ALL
@@ -124,6 +130,8 @@ pub fn parse_routing_outline(input: &str) -> IResult<&str, RoutingOutline> {
 /// of arcs and lines plus a height restriction. Portions of placement outlines on a panel that lie on a
 /// board in the panel are inherited by that board. Multiple placement outlines may be defined.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PlacementOutline {
     pub owner: String,       // MCAD, ECAD or UNOWNED
     pub board_side: String,  // TOP, BOTTOM or BOTH
@@ -163,6 +171,8 @@ pub fn parse_placement_outline(input: &str) -> IResult<&str, PlacementOutline> {
 /// Portions of routing keepouts on a panel that lie on a board in the panel are inherited by that board.
 /// Multiple keepouts are allowed.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RoutingKeepout {
     pub owner: String,          // MCAD, ECAD or UNOWNED
     pub routing_layers: String, // TOP, BOTTOM, BOTH, INNER or ALL
@@ -195,6 +205,8 @@ pub fn parse_routing_keepout(input: &str) -> IResult<&str, RoutingKeepout> {
 /// inherited by that board. Multiple via keepouts are allowed. Only through vias (vias that go all the
 /// way through the board) are supported.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ViaKeepout {
     pub owner: String, // MCAD, ECAD or UNOWNED
     pub outline: Vec<Point>,
@@ -224,6 +236,8 @@ pub fn parse_via_keepout(input: &str) -> IResult<&str, ViaKeepout> {
 /// placement keepouts on a panel that lie on a board in the panel are inherited by that board.
 /// Multiple keepouts are allowed.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PlacementKeepout {
     pub owner: String,       // MCAD, ECAD or UNOWNED
     pub board_side: String,  // TOP, BOTTOM or BOTH
@@ -263,6 +277,8 @@ pub fn parse_placement_keepout(input: &str) -> IResult<&str, PlacementKeepout> {
 /// a name designating the group of components to be placed in that area. Multiple areas are
 /// allowed.
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PlacementGroupArea {
     pub owner: String,      // MCAD, ECAD or UNOWNED
     pub board_side: String, // TOP, BOTTOM or BOTH
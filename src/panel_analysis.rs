@@ -0,0 +1,254 @@
+use crate::flatten::FlatPanel;
+use crate::geometry::{Polygon, mm_per_unit};
+
+/// The distance between two board instances.
+#[derive(Debug, PartialEq, Clone, Copy, PartialOrd)]
+pub struct BoardSpacing {
+    /// Indices of the two instances in `FlatPanel::boards`.
+    pub boards: (usize, usize),
+    /// Shortest distance between the board outlines in millimetres, zero if they overlap.
+    pub distance: f32,
+}
+
+/// A board instance overlapping a placement keepout of the panel, such as a rail.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct KeepoutIntrusion {
+    /// Index of the instance in `FlatPanel::boards`.
+    pub board: usize,
+    /// Index of the keepout in the panel's placement keepouts.
+    pub keepout: usize,
+}
+
+/// The clearance between a tooling hole of the panel and the nearest board instance.
+#[derive(Debug, PartialEq, Clone, Copy, PartialOrd)]
+pub struct ToolingHoleClearance {
+    /// Index of the hole in the panel's drilled holes.
+    pub hole: usize,
+    /// Index of the nearest instance in `FlatPanel::boards`, None if the panel has no boards.
+    pub board: Option<usize>,
+    /// Gap between the edge of the hole and the nearest board outline in millimetres, negative
+    /// if the hole cuts into the board.
+    pub clearance: f32,
+}
+
+/// Material utilisation and clearances of a panel. All lengths are in millimetres and areas in
+/// square millimetres, regardless of the units of the panel.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct PanelAnalysis {
+    /// Area of the panel outline less any cutouts.
+    pub panel_area: f32,
+    /// Total area of every board instance.
+    pub board_area: f32,
+    /// Fraction of the panel area used by boards, `board_area / panel_area`.
+    pub utilisation: f32,
+    /// The closest pair of board instances, None if there are fewer than two.
+    pub min_board_spacing: Option<BoardSpacing>,
+    /// Instances which are not entirely within the panel outline, or overlap a panel cutout.
+    pub boards_outside_panel: Vec<usize>,
+    /// Instances overlapping the panel's placement keepouts.
+    pub keepout_intrusions: Vec<KeepoutIntrusion>,
+    /// Clearance of every TOOL hole of the panel.
+    pub tooling_hole_clearances: Vec<ToolingHoleClearance>,
+}
+
+/// Analyse the material utilisation of a flattened panel and the clearances between its board
+/// instances, rails and tooling holes.
+///
+/// # Example
+/// ```
+/// use idf_parser::flatten::flatten_panel;
+/// use idf_parser::panel_analysis::analyse_panel;
+/// use idf_parser::parse_board_file;
+///
+/// let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let analysis = analyse_panel(&flatten_panel(&panel, &[board]).unwrap());
+/// assert!(analysis.utilisation > 0.0 && analysis.utilisation < 1.0);
+/// ```
+pub fn analyse_panel(flat: &FlatPanel) -> PanelAnalysis {
+    let scale = mm_per_unit(&flat.panel.header.units);
+    let tolerance = 0.01 / scale;
+
+    let panel = Polygon::from_outline(&flat.panel.outline.outline, tolerance);
+    let boards: Vec<Polygon> = flat
+        .boards
+        .iter()
+        .map(|b| Polygon::from_outline(&b.board.outline.outline, tolerance))
+        .collect();
+    let keepouts: Vec<Polygon> = flat
+        .panel
+        .placement_keepouts
+        .iter()
+        .map(|k| Polygon::from_outline(&k.outline, tolerance))
+        .collect();
+
+    let panel_area = panel.area() * scale * scale;
+    let board_area = boards.iter().map(|b| b.area()).sum::<f32>() * scale * scale;
+    let utilisation = if panel_area > 0.0 {
+        board_area / panel_area
+    } else {
+        0.0
+    };
+
+    let mut min_board_spacing: Option<BoardSpacing> = None;
+    for i in 0..boards.len() {
+        for j in i + 1..boards.len() {
+            let distance = boards[i].distance_to_polygon(&boards[j]) * scale;
+            if min_board_spacing.is_none_or(|s| distance < s.distance) {
+                min_board_spacing = Some(BoardSpacing {
+                    boards: (i, j),
+                    distance,
+                });
+            }
+        }
+    }
+
+    let boards_outside_panel = (0..boards.len())
+        .filter(|&i| !panel.encloses(&boards[i]))
+        .collect();
+
+    let mut keepout_intrusions = Vec::new();
+    for (board, polygon) in boards.iter().enumerate() {
+        for (keepout, keepout_polygon) in keepouts.iter().enumerate() {
+            if polygon.overlaps(keepout_polygon) {
+                keepout_intrusions.push(KeepoutIntrusion { board, keepout });
+            }
+        }
+    }
+
+    let tooling_hole_clearances = flat
+        .panel
+        .drilled_holes
+        .iter()
+        .enumerate()
+        .filter(|(_, hole)| hole.hole_type == "TOOL")
+        .map(|(i, hole)| {
+            let radius = hole.diameter / 2.0;
+            let nearest = boards
+                .iter()
+                .map(|b| {
+                    let distance = b.distance_to_boundary(hole.x, hole.y);
+                    if b.contains(hole.x, hole.y) {
+                        -distance - radius
+                    } else {
+                        distance - radius
+                    }
+                })
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            ToolingHoleClearance {
+                hole: i,
+                board: nearest.map(|(b, _)| b),
+                clearance: nearest.map_or(f32::INFINITY, |(_, c)| c * scale),
+            }
+        })
+        .collect();
+
+    PanelAnalysis {
+        panel_area,
+        board_area,
+        utilisation,
+        min_board_spacing,
+        boards_outside_panel,
+        keepout_intrusions,
+        tooling_hole_clearances,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::flatten_panel;
+    use crate::panelization::{PanelSpec, Rails, build_panel};
+    use crate::parse_board_file;
+
+    fn sample_analysis() -> PanelAnalysis {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        analyse_panel(&flatten_panel(&panel, &[board]).unwrap())
+    }
+
+    #[test]
+    fn test_utilisation() {
+        let analysis = sample_analysis();
+        let panel_area = 16000.0 * 12000.0 * 0.0254 * 0.0254;
+        assert!((analysis.panel_area - panel_area).abs() < 1.0);
+        assert!((analysis.utilisation - analysis.board_area / panel_area).abs() < 1e-4);
+        assert!(analysis.utilisation > 0.2 && analysis.utilisation < 0.5);
+    }
+
+    #[test]
+    fn test_spacing_and_keepouts() {
+        let analysis = sample_analysis();
+        // The TOP instance ends at x = 6887.5 and the mirrored BOTTOM instance starts at 8812.5.
+        let spacing = analysis.min_board_spacing.unwrap();
+        assert_eq!(spacing.boards, (0, 1));
+        assert!((spacing.distance - 1925.0 * 0.0254).abs() < 0.01);
+
+        assert!(analysis.boards_outside_panel.is_empty());
+        // Both instances overhang the rails of the sample panel.
+        assert_eq!(
+            analysis.keepout_intrusions,
+            [
+                KeepoutIntrusion {
+                    board: 0,
+                    keepout: 1
+                },
+                KeepoutIntrusion {
+                    board: 1,
+                    keepout: 0
+                }
+            ]
+        );
+        assert_eq!(analysis.tooling_hole_clearances.len(), 3);
+        assert!(
+            analysis
+                .tooling_hole_clearances
+                .iter()
+                .all(|c| c.clearance > 0.0)
+        );
+    }
+
+    #[test]
+    fn test_generated_panel() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let spec = PanelSpec {
+            columns: 2,
+            spacing_x: 100.0,
+            rails: Rails {
+                left: 500.0,
+                right: 500.0,
+                bottom: 0.0,
+                top: 0.0,
+            },
+            tooling_hole_diameter: 125.0,
+            tooling_hole_inset: 250.0,
+            alternate_sides: true,
+            ..Default::default()
+        };
+        let panel = build_panel(&board, &spec).unwrap();
+        let analysis = analyse_panel(&flatten_panel(&panel, &[board]).unwrap());
+
+        assert!((analysis.min_board_spacing.unwrap().distance - 100.0 * 0.0254).abs() < 0.01);
+        assert!(analysis.boards_outside_panel.is_empty());
+        assert!(analysis.keepout_intrusions.is_empty());
+        assert!(
+            analysis
+                .tooling_hole_clearances
+                .iter()
+                .all(|c| c.clearance > 0.0)
+        );
+    }
+
+    #[test]
+    fn test_board_outside_panel() {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let mut flat = flatten_panel(&panel, &[board]).unwrap();
+        for point in flat.boards[1].board.outline.outline.iter_mut() {
+            point.y += 5000.0;
+        }
+        let analysis = analyse_panel(&flat);
+        assert_eq!(analysis.boards_outside_panel, [1]);
+    }
+}
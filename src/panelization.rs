@@ -0,0 +1,346 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::drilled_holes::Hole;
+use crate::geometry::{Polygon, mm_per_unit};
+use crate::headers::{BoardPanelHeader, current_date};
+use crate::outlines::{BoardPanelOutline, PlacementKeepout};
+use crate::point::Point;
+
+/// A corner of a rectangular panel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Corner {
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+/// The width of the rails around the edges of a panel.
+#[derive(Debug, PartialEq, Clone, Copy, Default, PartialOrd)]
+pub struct Rails {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+/// Describes a step-and-repeat panel of a single board.
+///
+/// All lengths are in the units of the board being panelized.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct PanelSpec {
+    /// The name written to the panel header.
+    pub panel_name: String,
+    /// The part number of each board placement.
+    pub part_number: String,
+    /// The system id written to the panel header.
+    pub system_id: String,
+    /// The date written to the panel header, `yyyy/mm/dd.hh:mm:ss`.
+    pub date: String,
+    /// Number of boards along the x axis.
+    pub columns: u32,
+    /// Number of boards along the y axis.
+    pub rows: u32,
+    /// Gap between the bounding boxes of adjacent boards along the x axis.
+    pub spacing_x: f32,
+    /// Gap between the bounding boxes of adjacent boards along the y axis.
+    pub spacing_y: f32,
+    /// Rails around the array of boards. Each rail with a non-zero width is covered by a
+    /// placement keepout.
+    pub rails: Rails,
+    /// Diameter of the tooling holes, no tooling holes are added if this is zero.
+    pub tooling_hole_diameter: f32,
+    /// Distance of each tooling hole centre from both edges of its corner.
+    pub tooling_hole_inset: f32,
+    /// Corners which receive a tooling hole. Leaving one corner empty stops the panel being
+    /// loaded the wrong way round.
+    pub tooling_hole_corners: Vec<Corner>,
+    /// Place boards alternately on the TOP and BOTTOM of the panel in a checkerboard pattern.
+    pub alternate_sides: bool,
+}
+
+impl Default for PanelSpec {
+    fn default() -> Self {
+        PanelSpec {
+            panel_name: "panel".to_string(),
+            part_number: "".to_string(),
+            system_id: "idf-parser".to_string(),
+            date: current_date(),
+            columns: 1,
+            rows: 1,
+            spacing_x: 0.0,
+            spacing_y: 0.0,
+            rails: Rails::default(),
+            tooling_hole_diameter: 0.0,
+            tooling_hole_inset: 0.0,
+            tooling_hole_corners: vec![Corner::BottomLeft, Corner::TopLeft, Corner::TopRight],
+            alternate_sides: false,
+        }
+    }
+}
+
+/// A closed rectangular loop from (x0, y0) to (x1, y1).
+fn rectangle(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<Point> {
+    [(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)]
+        .iter()
+        .map(|&(x, y)| Point {
+            loop_label: 0,
+            x,
+            y,
+            angle: 0.0,
+        })
+        .collect()
+}
+
+/// Build a step-and-repeat panel of a board.
+///
+/// The panel outline is the rectangle enclosing the array of boards and the rails, with its
+/// lower left corner at the origin. Boards are laid out by their bounding box, so each cell
+/// is the size of the board plus the spacing. BOTTOM boards are mirrored about their y axis
+/// by the receiving system, which is accounted for when placing them.
+///
+/// # Example
+/// ```
+/// use idf_parser::panelization::{PanelSpec, Rails, build_panel};
+/// use idf_parser::parse_board_file;
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let spec = PanelSpec {
+///     panel_name: "sample_panel".to_string(),
+///     part_number: "pn-board".to_string(),
+///     columns: 2,
+///     spacing_x: 500.0,
+///     rails: Rails { left: 1000.0, right: 1000.0, bottom: 0.0, top: 0.0 },
+///     tooling_hole_diameter: 250.0,
+///     tooling_hole_inset: 500.0,
+///     alternate_sides: true,
+///     ..Default::default()
+/// };
+/// let panel = build_panel(&board, &spec).unwrap();
+/// assert_eq!(panel.component_placements.len(), 2);
+/// assert_eq!(panel.drilled_holes.len(), 3);
+/// ```
+pub fn build_panel(board: &BoardPanel, spec: &PanelSpec) -> Result<BoardPanel, String> {
+    if spec.columns == 0 || spec.rows == 0 {
+        return Err("A panel must have at least one row and one column.".to_string());
+    }
+    let lengths = [
+        spec.spacing_x,
+        spec.spacing_y,
+        spec.rails.left,
+        spec.rails.right,
+        spec.rails.bottom,
+        spec.rails.top,
+        spec.tooling_hole_diameter,
+        spec.tooling_hole_inset,
+    ];
+    if lengths
+        .iter()
+        .any(|length| !length.is_finite() || *length < 0.0)
+    {
+        return Err("Panel spacing, rails and tooling holes must not be negative.".to_string());
+    }
+
+    let units = &board.header.units;
+    let tolerance = 0.01 / mm_per_unit(units);
+    let bbox = Polygon::from_outline(&board.outline.outline, tolerance)
+        .bounding_box()
+        .ok_or_else(|| format!("Board {} has no outline.", board.header.board_name))?;
+
+    let pitch_x = bbox.width() + spec.spacing_x;
+    let pitch_y = bbox.height() + spec.spacing_y;
+    let width = spec.rails.left + spec.rails.right + pitch_x * spec.columns as f32 - spec.spacing_x;
+    let height = spec.rails.bottom + spec.rails.top + pitch_y * spec.rows as f32 - spec.spacing_y;
+
+    let mut component_placements = Vec::new();
+    for row in 0..spec.rows {
+        for column in 0..spec.columns {
+            let cell_x = spec.rails.left + pitch_x * column as f32;
+            let cell_y = spec.rails.bottom + pitch_y * row as f32;
+            let bottom = spec.alternate_sides && (row + column) % 2 == 1;
+            // Mirroring maps the board's x range to [-max_x, -min_x].
+            let x = if bottom {
+                cell_x + bbox.max_x
+            } else {
+                cell_x - bbox.min_x
+            };
+            component_placements.push(ComponentPlacement {
+                package_name: board.header.board_name.clone(),
+                part_number: spec.part_number.clone(),
+                reference_designator: "BOARD".to_string(),
+                x,
+                y: cell_y - bbox.min_y,
+                mounting_offset: 0.0,
+                rotation_angle: 0.0,
+                board_side: if bottom { "BOTTOM" } else { "TOP" }.to_string(),
+                placement_status: "MCAD".to_string(),
+            });
+        }
+    }
+
+    let rails = [
+        (
+            spec.rails.left,
+            rectangle(0.0, 0.0, spec.rails.left, height),
+        ),
+        (
+            spec.rails.right,
+            rectangle(width - spec.rails.right, 0.0, width, height),
+        ),
+        (
+            spec.rails.bottom,
+            rectangle(0.0, 0.0, width, spec.rails.bottom),
+        ),
+        (
+            spec.rails.top,
+            rectangle(0.0, height - spec.rails.top, width, height),
+        ),
+    ];
+    let placement_keepouts = rails
+        .into_iter()
+        .filter(|(rail_width, _)| *rail_width > 0.0)
+        .map(|(_, outline)| PlacementKeepout {
+            owner: "MCAD".to_string(),
+            board_side: "BOTH".to_string(),
+            keepout_height: 0.0,
+            outline,
+        })
+        .collect();
+
+    let mut drilled_holes = Vec::new();
+    if spec.tooling_hole_diameter > 0.0 {
+        let inset = spec.tooling_hole_inset;
+        for corner in spec.tooling_hole_corners.iter() {
+            let (x, y) = match corner {
+                Corner::BottomLeft => (inset, inset),
+                Corner::BottomRight => (width - inset, inset),
+                Corner::TopLeft => (inset, height - inset),
+                Corner::TopRight => (width - inset, height - inset),
+            };
+            drilled_holes.push(Hole {
+                diameter: spec.tooling_hole_diameter,
+                x,
+                y,
+                plating_style: "NPTH".to_string(),
+                associated_part: "PANEL".to_string(),
+                hole_type: "TOOL".to_string(),
+                owner: "MCAD".to_string(),
+            });
+        }
+    }
+
+    Ok(BoardPanel {
+        header: BoardPanelHeader {
+            file_type: "PANEL_FILE".to_string(),
+            version: 3,
+            system_id: spec.system_id.clone(),
+            date: spec.date.clone(),
+            file_version: 1,
+            board_name: spec.panel_name.clone(),
+            units: units.clone(),
+        },
+        outline: BoardPanelOutline {
+            owner: "MCAD".to_string(),
+            thickness: board.outline.thickness,
+            outline: rectangle(0.0, 0.0, width, height),
+        },
+        placement_keepouts,
+        drilled_holes,
+        component_placements,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board_or_panel;
+    use crate::parse_board_file;
+    use crate::writer::write_board_or_panel;
+
+    fn spec() -> PanelSpec {
+        PanelSpec {
+            panel_name: "sample_panel".to_string(),
+            part_number: "pn-board".to_string(),
+            date: "2024/01/01.00:00:00".to_string(),
+            columns: 2,
+            rows: 3,
+            spacing_x: 500.0,
+            spacing_y: 250.0,
+            rails: Rails {
+                left: 1000.0,
+                right: 1000.0,
+                bottom: 0.0,
+                top: 0.0,
+            },
+            tooling_hole_diameter: 250.0,
+            tooling_hole_inset: 500.0,
+            alternate_sides: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_panel() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let panel = build_panel(&board, &spec()).unwrap();
+
+        assert_eq!(panel.header.file_type, "PANEL_FILE");
+        assert_eq!(panel.header.units, "THOU");
+        assert_eq!(panel.outline.thickness, 62.0);
+
+        // The board is 5300 x 5900 thou including its arcs.
+        let corner = &panel.outline.outline[2];
+        assert_eq!(
+            (corner.x, corner.y),
+            (2000.0 + 5300.0 * 2.0 + 500.0, 5900.0 * 3.0 + 500.0)
+        );
+
+        assert_eq!(panel.placement_keepouts.len(), 2);
+        assert_eq!(panel.drilled_holes.len(), 3);
+        assert!(
+            panel
+                .drilled_holes
+                .iter()
+                .all(|hole| hole.hole_type == "TOOL"
+                    && hole.associated_part == "PANEL"
+                    && hole.plating_style == "NPTH")
+        );
+
+        assert_eq!(panel.component_placements.len(), 6);
+        let sides: Vec<&str> = panel
+            .component_placements
+            .iter()
+            .map(|placement| placement.board_side.as_str())
+            .collect();
+        assert_eq!(sides, ["TOP", "BOTTOM", "BOTTOM", "TOP", "TOP", "BOTTOM"]);
+
+        let first = &panel.component_placements[0];
+        assert_eq!((first.x, first.y), (1000.0 + 112.5, 400.0));
+        let second = &panel.component_placements[1];
+        assert_eq!((second.x, second.y), (1000.0 + 5800.0 + 5187.5, 400.0));
+    }
+
+    #[test]
+    fn test_build_panel_round_trip() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let panel = build_panel(&board, &spec()).unwrap();
+        let text = write_board_or_panel(&panel);
+        assert_eq!(parse_board_or_panel(&text).unwrap(), panel);
+    }
+
+    #[test]
+    fn test_build_panel_invalid_spec() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let empty = PanelSpec {
+            columns: 0,
+            ..spec()
+        };
+        assert!(build_panel(&board, &empty).is_err());
+        let negative = PanelSpec {
+            spacing_x: -1.0,
+            ..spec()
+        };
+        assert!(build_panel(&board, &negative).is_err());
+    }
+}
@@ -12,6 +12,8 @@ use nom::{IResult, Parser};
 /// First mention here:
 /// http://www.simplifiedsolutionsinc.com/images/idf_v30_spec.pdf#page=10 in Record 3
 #[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Point {
     /// The label of the loop the point exist in, 0 for counter-clockwise, 1 for clockwise.
     pub loop_label: u32,
@@ -46,7 +48,6 @@ pub fn point(input: &str) -> IResult<&str, Point> {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
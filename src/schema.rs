@@ -0,0 +1,79 @@
+//! JSON Schemas of the serialized [`BoardPanel`] and [`Library`], generated from the types with
+//! the `schema` feature.
+//!
+//! The schemas are versioned with the crate: their `$id` is `urn:idf-parser:<version>:board` or
+//! `urn:idf-parser:<version>:library`. Copies for the current version are kept in the `schema`
+//! directory of the repository.
+//!
+//! # Example
+//! ```
+//! use idf_parser::schema::board_schema;
+//!
+//! let schema = board_schema();
+//! assert!(schema.contains("\"title\": \"BoardPanel\""));
+//! ```
+
+use crate::board::BoardPanel;
+use crate::library::Library;
+use schemars::{JsonSchema, schema_for};
+
+/// The schema of `T` with a versioned `$id`, as pretty printed JSON.
+fn schema<T: JsonSchema>(name: &str) -> String {
+    let mut schema = schema_for!(T);
+    schema.insert(
+        "$id".to_string(),
+        format!("urn:idf-parser:{}:{}", env!("CARGO_PKG_VERSION"), name).into(),
+    );
+    format!("{:#}\n", schema.as_value())
+}
+
+/// The JSON Schema of a serialized [`BoardPanel`].
+pub fn board_schema() -> String {
+    schema::<BoardPanel>("board")
+}
+
+/// The JSON Schema of a serialized [`Library`].
+pub fn library_schema() -> String {
+    schema::<Library>("library")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare a schema with its copy in the repository, rewriting the copy instead when
+    /// `IDF_UPDATE_SCHEMA` is set.
+    fn check_schema(path: &str, schema: &str) {
+        if std::env::var_os("IDF_UPDATE_SCHEMA").is_some() {
+            std::fs::write(path, schema).unwrap();
+        }
+        let saved = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            saved == schema,
+            "{} is out of date, run `IDF_UPDATE_SCHEMA=1 cargo test --features schema` to update it",
+            path
+        );
+    }
+
+    #[test]
+    fn test_saved_schemas_are_current() {
+        check_schema("schema/board.schema.json", &board_schema());
+        check_schema("schema/library.schema.json", &library_schema());
+    }
+
+    #[test]
+    fn test_board_schema_describes_serialized_board() {
+        let board = crate::parse_board_file("src/test_files/board.emn").unwrap();
+        let value = serde_json::to_value(&board).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&board_schema()).unwrap();
+        let required = schema["required"].as_array().unwrap();
+        assert_eq!(required.len(), value.as_object().unwrap().len());
+        for key in required {
+            assert!(value.get(key.as_str().unwrap()).is_some());
+        }
+        assert_eq!(
+            schema["$id"],
+            format!("urn:idf-parser:{}:board", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use crate::board::BoardPanel;
+use crate::geometry::{Polygon, Transform, Vertex, mm_per_unit, signed_area};
+use crate::library::Library;
+use crate::point::Point;
+
+/// A solid made by extruding a polygon along z, with all lengths in millimetres.
+///
+/// The board lies between z = 0 and its thickness, so top side components sit above the
+/// board and bottom side components hang below z = 0.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct Extrusion {
+    /// The board name, or the reference designator of a component.
+    pub name: String,
+    /// The part number of a component, empty for the board.
+    pub part_number: String,
+    /// Index of the component's placement in the board's component placements, None for the
+    /// board.
+    pub placement: Option<usize>,
+    /// The outline in board coordinates.
+    pub polygon: Polygon,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+/// A triangle mesh with counter-clockwise triangles seen from outside the solid.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    /// The unit normal of a triangle, following the right hand rule.
+    pub fn normal(&self, triangle: &[usize; 3]) -> [f32; 3] {
+        let [a, b, c] = triangle.map(|i| self.vertices[i]);
+        let (u, v) = (
+            [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+            [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+        );
+        let n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        if length == 0.0 {
+            return [0.0, 0.0, 0.0];
+        }
+        n.map(|x| x / length)
+    }
+}
+
+impl Extrusion {
+    /// The outer ring counter-clockwise followed by the holes clockwise, so that the solid is
+    /// always on the left of each ring's edges.
+    pub fn oriented_rings(&self) -> Vec<Vec<Vertex>> {
+        self.polygon
+            .rings()
+            .enumerate()
+            .map(|(i, ring)| {
+                let mut ring = ring.clone();
+                if (signed_area(&ring) > 0.0) != (i == 0) {
+                    ring.reverse();
+                }
+                ring
+            })
+            .collect()
+    }
+
+    /// Triangulate the surface of the solid.
+    ///
+    /// The bottom face's vertices come first, in the order of the polygon's
+    /// [rings](Polygon::rings), followed by the top face's in the same order.
+    pub fn mesh(&self) -> Mesh {
+        let vertices: Vec<_> = self.polygon.rings().flatten().collect();
+        let n = vertices.len();
+        let mut mesh = Mesh {
+            vertices: [self.bottom, self.top]
+                .iter()
+                .flat_map(|&z| vertices.iter().map(move |&&(x, y)| [x, y, z]))
+                .collect(),
+            triangles: Vec::new(),
+        };
+        for [a, b, c] in self.polygon.triangulate() {
+            mesh.triangles.push([a + n, b + n, c + n]);
+            mesh.triangles.push([c, b, a]);
+        }
+
+        let mut start = 0;
+        for (r, ring) in self.polygon.rings().enumerate() {
+            let mut indices: Vec<usize> = (start..start + ring.len()).collect();
+            if (signed_area(ring) > 0.0) != (r == 0) {
+                indices.reverse();
+            }
+            start += ring.len();
+            for (i, &a) in indices.iter().enumerate() {
+                let b = indices[(i + 1) % indices.len()];
+                mesh.triangles.push([a, b, b + n]);
+                mesh.triangles.push([a, b + n, a + n]);
+            }
+        }
+        mesh
+    }
+}
+
+/// The board and every placed component as extruded solids in millimetres.
+///
+/// The board is extruded from its outline and thickness. Each component found in the library is
+/// extruded from its outline to its height, placed by its placement and raised off the board by
+/// its mounting offset. Components missing from the library are left out, as are UNPLACED
+/// placements, whose position has no meaning. Arcs are replaced with line segments deviating
+/// from them by no more than `tolerance` millimetres.
+///
+/// # Example
+/// ```
+/// use idf_parser::solids::board_extrusions;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let solids = board_extrusions(&board, Some(&library), 0.01);
+/// assert_eq!(solids.len(), 1 + board.component_placements.len());
+/// assert_eq!(solids[0].top, 62.0 * 0.0254);
+/// ```
+pub fn board_extrusions(
+    board: &BoardPanel,
+    library: Option<&Library>,
+    tolerance: f32,
+) -> Vec<Extrusion> {
+    let board_scale = mm_per_unit(&board.header.units);
+    let thickness = board.outline.thickness * board_scale;
+    let scaled = |points: &[Point], scale: f32, transform: &Transform| -> Vec<Point> {
+        points
+            .iter()
+            .map(|p| {
+                transform.apply_point(&Point {
+                    x: p.x * scale,
+                    y: p.y * scale,
+                    ..p.clone()
+                })
+            })
+            .collect()
+    };
+
+    let mut solids = vec![Extrusion {
+        name: board.header.board_name.clone(),
+        part_number: String::new(),
+        placement: None,
+        polygon: Polygon::from_outline(
+            &scaled(&board.outline.outline, board_scale, &Transform::default()),
+            tolerance,
+        ),
+        bottom: 0.0,
+        top: thickness,
+    }];
+
+    let Some(library) = library else {
+        return solids;
+    };
+    let index = library.index();
+    for (i, placement) in board.component_placements.iter().enumerate() {
+        if placement.placement_status == "UNPLACED" {
+            continue;
+        }
+        let Some(component) = index.for_placement(placement) else {
+            continue;
+        };
+        let scale = mm_per_unit(component.units());
+        // The placement is in board units, the component outline in its own units.
+        let transform = Transform {
+            x: placement.x * board_scale,
+            y: placement.y * board_scale,
+            ..Transform::from_placement(placement)
+        };
+        let outline = scaled(component.outline(), scale, &transform);
+        let (offset, height) = (
+            placement.mounting_offset * board_scale,
+            component.height() * scale,
+        );
+        let (bottom, top) = if placement.board_side == "BOTTOM" {
+            (-offset - height, -offset)
+        } else {
+            (thickness + offset, thickness + offset + height)
+        };
+        solids.push(Extrusion {
+            name: placement.reference_designator.clone(),
+            part_number: placement.part_number.clone(),
+            placement: Some(i),
+            polygon: Polygon::from_outline(&outline, tolerance),
+            bottom,
+            top,
+        });
+    }
+    solids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_board_extrusions() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let solids = board_extrusions(&board, Some(&library), 0.01);
+        let thickness = 62.0 * 0.0254;
+
+        // The board has a circular cutout.
+        assert_eq!(solids[0].polygon.holes.len(), 1);
+        let rings = solids[0].oriented_rings();
+        assert!(signed_area(&rings[0]) > 0.0 && signed_area(&rings[1]) < 0.0);
+
+        // C1 is on top, 100 thou off the board and 150 thou high.
+        let c1 = solids.iter().find(|s| s.name == "C1").unwrap();
+        assert!((c1.bottom - (thickness + 2.54)).abs() < 1e-5);
+        assert!((c1.top - c1.bottom - 150.0 * 0.0254).abs() < 1e-5);
+        let bbox = c1.polygon.bounding_box().unwrap();
+        assert!((bbox.min_x - (4000.0 - 55.0) * 0.0254).abs() < 1e-3);
+
+        // C3 is on the bottom, mirrored about its origin.
+        let c3 = solids.iter().find(|s| s.name == "C3").unwrap();
+        assert_eq!(c3.top, 0.0);
+        let bbox = c3.polygon.bounding_box().unwrap();
+        assert!((bbox.max_x - (3200.0 + 40.0) * 0.0254).abs() < 1e-3);
+        assert!(signed_area(&c3.oriented_rings()[0]) > 0.0);
+    }
+
+    #[test]
+    fn test_unplaced_components_are_left_out() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        board.component_placements[0].placement_status = "UNPLACED".to_string();
+        let solids = board_extrusions(&board, Some(&library), 0.05);
+        assert_eq!(solids.len(), board.component_placements.len());
+        assert!(solids.iter().all(|s| s.placement != Some(0)));
+        assert!(solids.iter().any(|s| s.placement == Some(1)));
+    }
+
+    #[test]
+    fn test_mesh_is_closed() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        for solid in board_extrusions(&board, Some(&library), 0.05) {
+            let mesh = solid.mesh();
+            // Every edge of a closed, consistently oriented mesh is used once in each direction.
+            let mut edges = std::collections::HashMap::new();
+            for t in &mesh.triangles {
+                for k in 0..3 {
+                    *edges.entry((t[k], t[(k + 1) % 3])).or_insert(0) += 1;
+                }
+            }
+            for (&(a, b), &count) in &edges {
+                assert_eq!(count, 1, "{}", solid.name);
+                assert_eq!(edges.get(&(b, a)), Some(&1), "{}", solid.name);
+            }
+
+            // The volume from the divergence theorem matches the extrusion.
+            let volume: f32 = mesh
+                .triangles
+                .iter()
+                .map(|t| {
+                    let [a, b, c] = t.map(|i| mesh.vertices[i]);
+                    (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                        + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                        / 6.0
+                })
+                .sum();
+            let expected = solid.polygon.area() * (solid.top - solid.bottom);
+            assert!(
+                (volume - expected).abs() < expected * 1e-3,
+                "{}",
+                solid.name
+            );
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use crate::board::BoardPanel;
+use crate::geometry::Vertex;
+use crate::headers::current_date;
+use crate::library::Library;
+use crate::solids::{Extrusion, board_extrusions};
+
+/// Escape a string for use inside a quoted STEP string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "''")
+}
+
+/// Format a number as a STEP real, which always has a decimal point.
+fn real(value: f32) -> String {
+    let text = format!("{}", value);
+    if text.contains('.') { text } else { text + "." }
+}
+
+/// Writes numbered STEP data section instances.
+struct Step {
+    output: String,
+    next: usize,
+}
+
+impl Step {
+    fn entity(&mut self, record: impl AsRef<str>) -> usize {
+        self.next += 1;
+        self.output
+            .push_str(&format!("#{}={};\n", self.next, record.as_ref()));
+        self.next
+    }
+
+    fn point(&mut self, x: f32, y: f32, z: f32) -> usize {
+        self.entity(format!(
+            "CARTESIAN_POINT('',({},{},{}))",
+            real(x),
+            real(y),
+            real(z)
+        ))
+    }
+
+    fn direction(&mut self, x: f32, y: f32, z: f32) -> usize {
+        self.entity(format!(
+            "DIRECTION('',({},{},{}))",
+            real(x),
+            real(y),
+            real(z)
+        ))
+    }
+
+    /// A product with its definition and the shape of the definition, returning all three.
+    fn product(
+        &mut self,
+        name: &str,
+        description: &str,
+        product_context: usize,
+        definition_context: usize,
+    ) -> (usize, usize, usize) {
+        let product = self.entity(format!(
+            "PRODUCT('{}','{}','{}',(#{}))",
+            name, name, description, product_context
+        ));
+        let formation = self.entity(format!("PRODUCT_DEFINITION_FORMATION('','',#{})", product));
+        let definition = self.entity(format!(
+            "PRODUCT_DEFINITION('design','',#{},#{})",
+            formation, definition_context
+        ));
+        let shape = self.entity(format!("PRODUCT_DEFINITION_SHAPE('','',#{})", definition));
+        (product, definition, shape)
+    }
+
+    fn list(ids: impl IntoIterator<Item = usize>) -> String {
+        let ids: Vec<String> = ids.into_iter().map(|id| format!("#{}", id)).collect();
+        format!("({})", ids.join(","))
+    }
+
+    /// A planar face bounded by loops of point instances, the first being the outer bound.
+    /// The plane's normal is `normal` and `reference` is a direction lying in it.
+    fn face(&mut self, loops: &[Vec<usize>], normal: (f32, f32, f32), reference: Vertex) -> usize {
+        let mut bounds = Vec::new();
+        for (i, points) in loops.iter().enumerate() {
+            let poly_loop = self.entity(format!("POLY_LOOP('',{})", Step::list(points.clone())));
+            let kind = if i == 0 {
+                "FACE_OUTER_BOUND"
+            } else {
+                "FACE_BOUND"
+            };
+            bounds.push(self.entity(format!("{}('',#{},.T.)", kind, poly_loop)));
+        }
+        let location = loops[0][0];
+        let axis = self.direction(normal.0, normal.1, normal.2);
+        let ref_direction = self.direction(reference.0, reference.1, 0.0);
+        let placement = self.entity(format!(
+            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
+            location, axis, ref_direction
+        ));
+        let plane = self.entity(format!("PLANE('',#{})", placement));
+        self.entity(format!(
+            "FACE_SURFACE('',{},#{},.T.)",
+            Step::list(bounds),
+            plane
+        ))
+    }
+
+    /// A closed faceted solid of an extrusion: a top and bottom face with the polygon's holes,
+    /// and a rectangular side face for every edge of every ring.
+    fn solid(&mut self, extrusion: &Extrusion) -> usize {
+        let rings = extrusion.oriented_rings();
+        let mut levels = [Vec::new(), Vec::new()];
+        for (level, z) in levels.iter_mut().zip([extrusion.bottom, extrusion.top]) {
+            for ring in &rings {
+                let ids: Vec<usize> = ring.iter().map(|&(x, y)| self.point(x, y, z)).collect();
+                level.push(ids);
+            }
+        }
+        let [bottom, top] = levels;
+
+        let mut faces = vec![self.face(&top, (0.0, 0.0, 1.0), (1.0, 0.0))];
+        let reversed: Vec<Vec<usize>> = bottom
+            .iter()
+            .map(|ids| ids.iter().rev().copied().collect())
+            .collect();
+        faces.push(self.face(&reversed, (0.0, 0.0, -1.0), (1.0, 0.0)));
+
+        // The solid is on the left of each ring edge, so the outward normal is on the right.
+        for (r, ring) in rings.iter().enumerate() {
+            for i in 0..ring.len() {
+                let j = (i + 1) % ring.len();
+                let (dx, dy) = (ring[j].0 - ring[i].0, ring[j].1 - ring[i].1);
+                let length = (dx * dx + dy * dy).sqrt();
+                if length == 0.0 {
+                    continue;
+                }
+                let quad = vec![bottom[r][i], bottom[r][j], top[r][j], top[r][i]];
+                faces.push(self.face(
+                    &[quad],
+                    (dy / length, -dx / length, 0.0),
+                    (dx / length, dy / length),
+                ));
+            }
+        }
+
+        let shell = self.entity(format!("CLOSED_SHELL('',{})", Step::list(faces)));
+        self.entity(format!("FACETED_BREP('',#{})", shell))
+    }
+}
+
+/// Write the board and its placed components as an AP214 STEP file.
+///
+/// The board is a solid extruded from its outline to its thickness with its cutouts, and each
+/// component found in the library is a prism extruded from its outline to its height at its
+/// placement, with bottom side components below the board. See
+/// [board_extrusions](crate::solids::board_extrusions) for how the solids are built.
+///
+/// Each solid is its own product, used once in an assembly product named after the board with
+/// the description `assembly`. The board's product is also named after the board, and each
+/// component's after its reference designator, with its part number as the description. All
+/// geometry is in millimetres in board coordinates, so every product is placed at the
+/// assembly's origin. Arcs are replaced by flat faces deviating from them by no more than
+/// `tolerance` millimetres.
+///
+/// # Example
+/// ```
+/// use idf_parser::step::write_step;
+/// use idf_parser::{parse_board_file, parse_library_file};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let step = write_step(&board, Some(&library), 0.05);
+/// assert!(step.starts_with("ISO-10303-21;"));
+/// assert!(step.contains("PRODUCT('C1','C1','pn-cap',"));
+/// ```
+pub fn write_step(board: &BoardPanel, library: Option<&Library>, tolerance: f32) -> String {
+    let mut step = Step {
+        output: String::new(),
+        next: 0,
+    };
+
+    let application =
+        step.entity("APPLICATION_CONTEXT('core data for automotive mechanical design processes')");
+    step.entity(format!(
+        "APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{})",
+        application
+    ));
+    let product_context = step.entity(format!("PRODUCT_CONTEXT('',#{},'mechanical')", application));
+    let definition_context = step.entity(format!(
+        "PRODUCT_DEFINITION_CONTEXT('part definition',#{},'design')",
+        application
+    ));
+    let length = step.entity("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))");
+    let angle = step.entity("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))");
+    let solid_angle = step.entity("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())");
+    let uncertainty = step.entity(format!(
+        "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-06),#{},'distance_accuracy_value','confusion accuracy')",
+        length
+    ));
+    let context = step.entity(format!(
+        "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))",
+        uncertainty, length, angle, solid_angle
+    ));
+    let origin = step.point(0.0, 0.0, 0.0);
+    let z_axis = step.direction(0.0, 0.0, 1.0);
+    let x_axis = step.direction(1.0, 0.0, 0.0);
+    let axes = step.entity(format!(
+        "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
+        origin, z_axis, x_axis
+    ));
+
+    // The assembly of the board and its components, all placed at its origin as their solids
+    // are already in board coordinates.
+    let board_name = escape(&board.header.board_name);
+    let (assembly, assembly_definition, assembly_shape) =
+        step.product(&board_name, "assembly", product_context, definition_context);
+    let assembly_representation = step.entity(format!(
+        "SHAPE_REPRESENTATION('{}',(#{}),#{})",
+        board_name, axes, context
+    ));
+    step.entity(format!(
+        "SHAPE_DEFINITION_REPRESENTATION(#{},#{})",
+        assembly_shape, assembly_representation
+    ));
+    let mut products = vec![assembly];
+
+    let solids = board_extrusions(board, library, tolerance)
+        .into_iter()
+        .filter(|s| !s.polygon.is_empty() && s.top > s.bottom);
+    for (i, extrusion) in solids.enumerate() {
+        let name = escape(&extrusion.name);
+        let (product, definition, shape) = step.product(
+            &name,
+            &escape(&extrusion.part_number),
+            product_context,
+            definition_context,
+        );
+        products.push(product);
+        let brep = step.solid(&extrusion);
+        let representation = step.entity(format!(
+            "FACETED_BREP_SHAPE_REPRESENTATION('{}',(#{},#{}),#{})",
+            name, brep, axes, context
+        ));
+        step.entity(format!(
+            "SHAPE_DEFINITION_REPRESENTATION(#{},#{})",
+            shape, representation
+        ));
+
+        let occurrence = step.entity(format!(
+            "NEXT_ASSEMBLY_USAGE_OCCURRENCE('{}','{}','',#{},#{},$)",
+            i + 1,
+            name,
+            assembly_definition,
+            definition
+        ));
+        let occurrence_shape =
+            step.entity(format!("PRODUCT_DEFINITION_SHAPE('','',#{})", occurrence));
+        let transformation = step.entity(format!(
+            "ITEM_DEFINED_TRANSFORMATION('','',#{},#{})",
+            axes, axes
+        ));
+        let relationship = step.entity(format!(
+            "(REPRESENTATION_RELATIONSHIP('','',#{},#{})REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION(#{})SHAPE_REPRESENTATION_RELATIONSHIP())",
+            representation, assembly_representation, transformation
+        ));
+        step.entity(format!(
+            "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION(#{},#{})",
+            relationship, occurrence_shape
+        ));
+    }
+    step.entity(format!(
+        "PRODUCT_RELATED_PRODUCT_CATEGORY('part',$,{})",
+        Step::list(products)
+    ));
+
+    let timestamp = current_date().replace('/', "-").replace('.', "T");
+    format!(
+        "ISO-10303-21;\nHEADER;\nFILE_DESCRIPTION(('IDF board assembly'),'2;1');\n\
+         FILE_NAME('{}','{}',(''),(''),'idf_parser','idf_parser','');\n\
+         FILE_SCHEMA(('AUTOMOTIVE_DESIGN {{ 1 0 10303 214 1 1 1 1 }}'));\nENDSEC;\n\
+         DATA;\n{}ENDSEC;\nEND-ISO-10303-21;\n",
+        escape(&board.header.board_name),
+        timestamp,
+        step.output
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_write_step() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let step = write_step(&board, Some(&library), 0.05);
+        let solids = board_extrusions(&board, Some(&library), 0.05);
+
+        let count = |name: &str| step.matches(&format!("={}(", name)).count();
+        assert_eq!(count("PRODUCT"), solids.len() + 1);
+        assert_eq!(count("FACETED_BREP"), solids.len());
+
+        // One assembly holds an occurrence of every solid's product.
+        assert!(step.contains(&format!(
+            "PRODUCT('{}','{}','assembly',",
+            board.header.board_name, board.header.board_name
+        )));
+        assert_eq!(count("NEXT_ASSEMBLY_USAGE_OCCURRENCE"), solids.len());
+        assert_eq!(
+            count("CONTEXT_DEPENDENT_SHAPE_REPRESENTATION"),
+            solids.len()
+        );
+        assert!(step.contains("NEXT_ASSEMBLY_USAGE_OCCURRENCE('2','C1','',"));
+        // The assembly's definition follows its product and product definition formation.
+        let assembly: usize = step
+            .lines()
+            .find(|l| l.contains("'assembly'"))
+            .and_then(|l| l[1..].split('=').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let definition = format!("#{}=PRODUCT_DEFINITION(", assembly + 2);
+        assert!(step.contains(&definition));
+        let uses = step
+            .lines()
+            .filter(|l| l.contains("NEXT_ASSEMBLY_USAGE_OCCURRENCE("))
+            .filter(|l| l.contains(&format!("'',#{},#", assembly + 2)))
+            .count();
+        assert_eq!(uses, solids.len());
+        assert!(step.contains(&format!(
+            "PRODUCT('{}','{}','',",
+            board.header.board_name, board.header.board_name
+        )));
+        assert!(step.contains("PRODUCT('U1','U1','pn-pal16l8-plcc',"));
+
+        // Every face has a top, a bottom, and a side per ring edge.
+        let faces: usize = solids
+            .iter()
+            .map(|s| 2 + s.polygon.rings().map(|r| r.len()).sum::<usize>())
+            .sum();
+        assert_eq!(count("FACE_SURFACE"), faces);
+
+        // Every reference is to an earlier instance.
+        let mut defined = HashMap::new();
+        for line in step.lines().filter(|l| l.starts_with('#')) {
+            let (id, record) = line.split_once('=').unwrap();
+            for reference in record.split('#').skip(1) {
+                let number: String = reference
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                assert!(defined.contains_key(&number), "{} in {}", number, line);
+            }
+            defined.insert(id[1..].to_string(), ());
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use crate::board::BoardPanel;
+use crate::drilled_holes::Hole;
+use crate::geometry::{
+    BoundingBox, Polygon, Transform, arc_centre, is_full_circle, mm_per_unit, split_loops,
+    tessellate_loop,
+};
+use crate::library::Library;
+use crate::point::Point;
+
+/// A category of entity which can be shown or hidden in an SVG rendering.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Layer {
+    Outline,
+    OtherOutlines,
+    RoutingOutlines,
+    PlacementOutlines,
+    RoutingKeepouts,
+    ViaKeepouts,
+    PlacementKeepouts,
+    PlacementGroupAreas,
+    Holes,
+    /// Outlines of placed components, drawn when a library is supplied.
+    Components,
+    /// Reference designators of placed components.
+    Labels,
+    Notes,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 12] = [
+        Layer::Outline,
+        Layer::OtherOutlines,
+        Layer::RoutingOutlines,
+        Layer::PlacementOutlines,
+        Layer::RoutingKeepouts,
+        Layer::ViaKeepouts,
+        Layer::PlacementKeepouts,
+        Layer::PlacementGroupAreas,
+        Layer::Holes,
+        Layer::Components,
+        Layer::Labels,
+        Layer::Notes,
+    ];
+
+    /// The CSS class of the layer's SVG group.
+    pub fn class(&self) -> &'static str {
+        match self {
+            Layer::Outline => "outline",
+            Layer::OtherOutlines => "other-outline",
+            Layer::RoutingOutlines => "routing-outline",
+            Layer::PlacementOutlines => "placement-outline",
+            Layer::RoutingKeepouts => "routing-keepout",
+            Layer::ViaKeepouts => "via-keepout",
+            Layer::PlacementKeepouts => "placement-keepout",
+            Layer::PlacementGroupAreas => "placement-group-area",
+            Layer::Holes => "holes",
+            Layer::Components => "components",
+            Layer::Labels => "labels",
+            Layer::Notes => "notes",
+        }
+    }
+}
+
+/// What to include in an SVG rendering.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct SvgOptions {
+    /// Only draw entities on this side, TOP or BOTTOM, along with those on both sides. None
+    /// draws every side.
+    pub side: Option<String>,
+    /// The layers to draw.
+    pub layers: Vec<Layer>,
+    /// Space around the drawing as a fraction of its larger dimension.
+    pub margin: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            side: None,
+            layers: Layer::ALL.to_vec(),
+            margin: 0.05,
+        }
+    }
+}
+
+const STYLE: &str = "path, circle { vector-effect: non-scaling-stroke; stroke-width: 1; }
+.outline path { fill: #2e7d32; fill-opacity: 0.2; fill-rule: evenodd; stroke: #1b5e20; stroke-width: 2; }
+.other-outline path { fill: #6d4c41; fill-opacity: 0.15; fill-rule: evenodd; stroke: #6d4c41; }
+.routing-outline path { fill: none; stroke: #1565c0; stroke-dasharray: 6 3; }
+.placement-outline path { fill: none; stroke: #6a1b9a; stroke-dasharray: 6 3; }
+.routing-keepout path { fill: #1565c0; fill-opacity: 0.15; fill-rule: evenodd; stroke: #1565c0; }
+.via-keepout path { fill: #ef6c00; fill-opacity: 0.15; fill-rule: evenodd; stroke: #ef6c00; }
+.placement-keepout path { fill: #c62828; fill-opacity: 0.15; fill-rule: evenodd; stroke: #c62828; }
+.placement-group-area path { fill: none; stroke: #00838f; stroke-dasharray: 2 2; }
+.holes circle { fill: #ffffff; stroke: #000000; }
+.holes .pin { fill: #ffd54f; }
+.holes .via { fill: #90a4ae; }
+.holes .mtg { fill: #ffffff; stroke-width: 2; }
+.holes .tool { fill: #ffffff; stroke: #d50000; stroke-width: 2; }
+.components path { fill: #9e9e9e; fill-opacity: 0.4; fill-rule: evenodd; stroke: #424242; }
+.components .bottom { fill-opacity: 0.2; stroke-dasharray: 4 2; }
+text { font-family: sans-serif; fill: #212121; }
+.labels text { text-anchor: middle; dominant-baseline: central; }
+";
+
+/// Deviation from true arcs allowed when finding the extent of a drawing, in drawing units.
+const BOUNDS_TOLERANCE: f32 = 0.01;
+
+/// Escape text for use in SVG content or attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Collects the SVG elements of a drawing along with their extent.
+struct Canvas {
+    body: String,
+    bounds: Option<BoundingBox>,
+}
+
+impl Canvas {
+    fn include(&mut self, x: f32, y: f32) {
+        match self.bounds.as_mut() {
+            Some(bounds) => bounds.include(x, y),
+            None => {
+                self.bounds = Some(BoundingBox {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                })
+            }
+        }
+    }
+
+    /// Add a path tracing every loop of an IDF outline, with arcs drawn as true SVG arcs.
+    ///
+    /// The y axis is flipped, since IDF y points up and SVG y points down.
+    fn path(&mut self, points: &[Point], class: Option<&str>) {
+        let mut data = String::new();
+        for points in split_loops(points) {
+            let Some(first) = points.first() else {
+                continue;
+            };
+            for (x, y) in tessellate_loop(points, BOUNDS_TOLERANCE) {
+                self.include(x, y);
+            }
+            if let Some(end) = points.get(1).filter(|p| is_full_circle(p.angle)) {
+                let radius = ((end.x - first.x).powi(2) + (end.y - first.y).powi(2)).sqrt();
+                data.push_str(&format!(
+                    "M {} {} A {r} {r} 0 1 0 {} {} A {r} {r} 0 1 0 {} {} Z ",
+                    first.x + radius,
+                    -first.y,
+                    first.x - radius,
+                    -first.y,
+                    first.x + radius,
+                    -first.y,
+                    r = radius
+                ));
+                continue;
+            }
+            data.push_str(&format!("M {} {} ", first.x, -first.y));
+            for pair in points.windows(2) {
+                let (start, end) = (&pair[0], &pair[1]);
+                if end.angle.abs() > 1e-6 && (start.x, start.y) != (end.x, end.y) {
+                    let (_, radius) = arc_centre((start.x, start.y), (end.x, end.y), end.angle);
+                    // Counter-clockwise in IDF is clockwise once y is flipped.
+                    data.push_str(&format!(
+                        "A {r} {r} 0 {} {} {} {} ",
+                        (end.angle.abs() > 180.0) as u8,
+                        (end.angle < 0.0) as u8,
+                        end.x,
+                        -end.y,
+                        r = radius
+                    ));
+                } else {
+                    data.push_str(&format!("L {} {} ", end.x, -end.y));
+                }
+            }
+            data.push_str("Z ");
+        }
+        let class = class
+            .map(|c| format!(" class=\"{}\"", c))
+            .unwrap_or_default();
+        self.body
+            .push_str(&format!("<path{} d=\"{}\"/>\n", class, data.trim_end()));
+    }
+
+    fn hole(&mut self, hole: &Hole) {
+        let radius = hole.diameter / 2.0;
+        self.body.push_str(&format!(
+            "<circle class=\"{} {}\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+            hole.hole_type.to_lowercase(),
+            hole.plating_style.to_lowercase(),
+            hole.x,
+            -hole.y,
+            radius
+        ));
+        self.include(hole.x - radius, hole.y - radius);
+        self.include(hole.x + radius, hole.y + radius);
+    }
+
+    fn text(&mut self, x: f32, y: f32, height: f32, text: &str) {
+        self.body.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>\n",
+            x,
+            -y,
+            height,
+            escape(text)
+        ));
+        self.include(x, y);
+    }
+
+    fn group(&mut self, layer: Layer, draw: impl FnOnce(&mut Canvas)) {
+        self.body
+            .push_str(&format!("<g class=\"{}\">\n", layer.class()));
+        draw(self);
+        self.body.push_str("</g>\n");
+    }
+}
+
+/// Render a board or panel as an SVG image.
+///
+/// The board outline and cutouts, other, routing and placement outlines, keepouts, placement
+/// group areas, drilled holes and notes are drawn in board units, each category in its own
+/// group with a CSS class named after its [`Layer`]. Arcs are drawn as true arcs. When a library
+/// is given, the outline of every placed component is drawn at its placement along with its
+/// reference designator.
+///
+/// Entities on both sides, or on a layer such as ALL or INNER, are drawn whichever side is
+/// selected in the options. Drilled holes go through the board and are always drawn.
+///
+/// # Example
+/// ```
+/// use idf_parser::parse_board_file;
+/// use idf_parser::svg::{Layer, SvgOptions, render_svg};
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let options = SvgOptions {
+///     side: Some("TOP".to_string()),
+///     layers: vec![Layer::Outline, Layer::Holes],
+///     ..Default::default()
+/// };
+/// let svg = render_svg(&board, None, &options);
+/// assert!(svg.starts_with("<svg"));
+/// ```
+pub fn render_svg(board: &BoardPanel, library: Option<&Library>, options: &SvgOptions) -> String {
+    let shown = |layer: Layer| options.layers.contains(&layer);
+    let on_side = |side: &str| match (options.side.as_deref(), side) {
+        (Some(selected), "TOP" | "BOTTOM") => selected == side,
+        _ => true,
+    };
+    let mut canvas = Canvas {
+        body: String::new(),
+        bounds: None,
+    };
+
+    if shown(Layer::Outline) {
+        canvas.group(Layer::Outline, |c| c.path(&board.outline.outline, None));
+    }
+    if shown(Layer::OtherOutlines) {
+        canvas.group(Layer::OtherOutlines, |c| {
+            for o in board
+                .other_outlines
+                .iter()
+                .filter(|o| on_side(&o.board_side))
+            {
+                c.path(&o.outline, None);
+            }
+        });
+    }
+    if shown(Layer::RoutingOutlines) {
+        canvas.group(Layer::RoutingOutlines, |c| {
+            for o in board
+                .routing_outlines
+                .iter()
+                .filter(|o| on_side(&o.routing_layers))
+            {
+                c.path(&o.outline, None);
+            }
+        });
+    }
+    if shown(Layer::PlacementOutlines) {
+        canvas.group(Layer::PlacementOutlines, |c| {
+            for o in board
+                .placement_outlines
+                .iter()
+                .filter(|o| on_side(&o.board_side))
+            {
+                c.path(&o.outline, None);
+            }
+        });
+    }
+    if shown(Layer::RoutingKeepouts) {
+        canvas.group(Layer::RoutingKeepouts, |c| {
+            for k in board
+                .routing_keepouts
+                .iter()
+                .filter(|k| on_side(&k.routing_layers))
+            {
+                c.path(&k.outline, None);
+            }
+        });
+    }
+    if shown(Layer::ViaKeepouts) {
+        canvas.group(Layer::ViaKeepouts, |c| {
+            for k in board.via_keepouts.iter() {
+                c.path(&k.outline, None);
+            }
+        });
+    }
+    if shown(Layer::PlacementKeepouts) {
+        canvas.group(Layer::PlacementKeepouts, |c| {
+            for k in board
+                .placement_keepouts
+                .iter()
+                .filter(|k| on_side(&k.board_side))
+            {
+                c.path(&k.outline, None);
+            }
+        });
+    }
+    if shown(Layer::PlacementGroupAreas) {
+        canvas.group(Layer::PlacementGroupAreas, |c| {
+            for a in board
+                .placement_group_areas
+                .iter()
+                .filter(|a| on_side(&a.board_side))
+            {
+                c.path(&a.outline, None);
+            }
+        });
+    }
+    if shown(Layer::Holes) {
+        canvas.group(Layer::Holes, |c| {
+            for hole in board.drilled_holes.iter() {
+                c.hole(hole);
+            }
+        });
+    }
+
+    let placements: Vec<_> = board
+        .component_placements
+        .iter()
+        .filter(|p| on_side(&p.board_side))
+        .collect();
+    if let Some(library) = library
+        && shown(Layer::Components)
+    {
+        let index = library.index();
+        let board_scale = mm_per_unit(&board.header.units);
+        canvas.group(Layer::Components, |c| {
+            for placement in placements.iter() {
+                let Some(component) = index.for_placement(placement) else {
+                    continue;
+                };
+                let scale = mm_per_unit(component.units()) / board_scale;
+                let transform = Transform::from_placement(placement);
+                let outline: Vec<Point> = component
+                    .outline()
+                    .iter()
+                    .map(|p| {
+                        transform.apply_point(&Point {
+                            x: p.x * scale,
+                            y: p.y * scale,
+                            ..p.clone()
+                        })
+                    })
+                    .collect();
+                c.path(&outline, Some(&placement.board_side.to_lowercase()));
+            }
+        });
+    }
+
+    // Size labels relative to the board, since placements carry no text height.
+    let label_height = Polygon::from_outline(&board.outline.outline, 0.0)
+        .bounding_box()
+        .map_or(1.0, |b| b.width().max(b.height()) / 80.0);
+    if shown(Layer::Labels) {
+        canvas.group(Layer::Labels, |c| {
+            for placement in placements.iter() {
+                c.text(
+                    placement.x,
+                    placement.y,
+                    label_height,
+                    &placement.reference_designator,
+                );
+            }
+        });
+    }
+    if shown(Layer::Notes) {
+        canvas.group(Layer::Notes, |c| {
+            for note in board.notes.iter() {
+                c.text(note.x, note.y, note.text_height, &note.text);
+            }
+        });
+    }
+
+    let bounds = canvas.bounds.unwrap_or_default();
+    let margin = bounds.width().max(bounds.height()) * options.margin;
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        bounds.min_x - margin,
+        -bounds.max_y - margin,
+        bounds.width() + 2.0 * margin,
+        bounds.height() + 2.0 * margin
+    );
+    output.push_str(&format!(
+        "<title>{}</title>\n<style>\n{}</style>\n",
+        escape(&board.header.board_name),
+        STYLE
+    ));
+    output.push_str(&canvas.body);
+    output.push_str("</svg>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board_file, parse_library_file};
+
+    fn count(svg: &str, pattern: &str) -> usize {
+        svg.matches(pattern).count()
+    }
+
+    #[test]
+    fn test_render_board() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let svg = render_svg(&board, None, &SvgOptions::default());
+        assert_eq!(count(&svg, "<circle"), board.drilled_holes.len());
+        assert_eq!(count(&svg, "class=\"tool npth\""), 1);
+        // The outline has two semicircular notches and a circular cutout.
+        let outline = svg.split("<g class=\"outline\">").nth(1).unwrap();
+        let outline = &outline[..outline.find("</g>").unwrap()];
+        assert_eq!(count(outline, " A "), 4);
+        assert_eq!(count(outline, "M "), 2);
+        assert!(svg.contains(">Do not move connectors!</text>"));
+        // Without a library there are no component outlines, only labels.
+        assert_eq!(count(&svg, "<g class=\"components\">"), 0);
+        assert_eq!(count(&svg, ">C1</text>"), 1);
+        // The view box covers the outline, which extends to y = -400 and y = 5500.
+        assert!(svg.contains("viewBox=\"-407.5 -5795 5890 6490\""));
+    }
+
+    #[test]
+    fn test_render_filters() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let svg = render_svg(&board, Some(&library), &SvgOptions::default());
+        assert_eq!(count(&svg, "<path class=\"top\""), 8);
+        assert_eq!(count(&svg, "<path class=\"bottom\""), 3);
+
+        let options = SvgOptions {
+            side: Some("BOTTOM".to_string()),
+            layers: vec![Layer::PlacementOutlines, Layer::Components, Layer::Labels],
+            ..Default::default()
+        };
+        let svg = render_svg(&board, Some(&library), &options);
+        assert_eq!(count(&svg, "<path class=\"top\""), 0);
+        assert_eq!(count(&svg, "<path class=\"bottom\""), 3);
+        assert_eq!(count(&svg, "<circle"), 0);
+        assert_eq!(count(&svg, "<text"), 3);
+        let outlines = svg.split("<g class=\"placement-outline\">").nth(1).unwrap();
+        assert_eq!(
+            count(&outlines[..outlines.find("</g>").unwrap()], "<path"),
+            1
+        );
+    }
+}
@@ -1,8 +1,389 @@
+//! Validation of boards, panels and libraries.
+//!
+//! Validation runs a set of rules over the parsed files and collects every finding into a
+//! [`ValidationReport`] rather than stopping at the first problem. Each rule has a stable id and a
+//! default [`Severity`], both of which can be overridden with a [`ValidationConfig`].
+//!
+//! # Example
+//! ```
+//! use idf_parser::{parse_board_file, parse_library_file};
+//! use idf_parser::validation::{Severity, ValidationConfig, ValidationInput, Validator};
+//!
+//! let board = parse_board_file("src/test_files/board.emn").unwrap();
+//! let library = parse_library_file("src/test_files/library.emp").unwrap();
+//!
+//! let mut config = ValidationConfig::default();
+//! config.disable("hole.hole_to_edge_clearance");
+//! config.set_severity("hole.duplicate", Severity::Error);
+//!
+//! let boards = [board];
+//! let libraries = [library];
+//! let input = ValidationInput {
+//!     panel: None,
+//!     boards: &boards,
+//!     libraries: &libraries,
+//! };
+//! let report = Validator::new(config).validate(&input);
+//! assert!(!report.has_errors());
+//! println!("{}", report.to_text());
+//! ```
+
 use crate::board::BoardPanel;
+use crate::components::Component;
+use crate::hole_checks::{HoleCheckConfig, HoleRule, check_hole_rule};
 use crate::library::Library;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+/// How serious a finding is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// Parse a severity from its lower case name.
+    pub fn parse(input: &str) -> Option<Severity> {
+        match input {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A single problem found by a rule.
+#[derive(Debug, PartialEq, Clone, PartialOrd)]
+pub struct Finding {
+    pub rule_id: String,
+    pub severity: Severity,
+    /// The entities involved, written as `kind:identifier`, e.g. `placement:C1` or `hole:12`.
+    pub entities: Vec<String>,
+    /// Name of the board or panel the finding relates to. Libraries have no name of their own,
+    /// so are written as `library[i]`, `i` being the index into [ValidationInput::libraries].
+    pub source: Option<String>,
+    /// Position of the finding in the units of the source file.
+    pub location: Option<(f32, f32)>,
+    pub message: String,
+}
+
+/// Every finding from a validation run.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Number of findings with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// All findings produced by the given rule.
+    pub fn by_rule<'a>(&'a self, rule_id: &'a str) -> impl Iterator<Item = &'a Finding> + 'a {
+        self.findings.iter().filter(move |f| f.rule_id == rule_id)
+    }
+
+    /// Render the report as human readable text, one finding per line followed by a summary.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for finding in self.findings.iter() {
+            text.push_str(&format!(
+                "{}[{}]",
+                finding.severity.as_str(),
+                finding.rule_id
+            ));
+            if let Some(source) = &finding.source {
+                text.push_str(&format!(" {}", source));
+            }
+            if let Some((x, y)) = finding.location {
+                text.push_str(&format!(" ({}, {})", x, y));
+            }
+            text.push_str(&format!(": {}\n", finding.message));
+        }
+        text.push_str(&format!(
+            "{} error(s), {} warning(s), {} info\n",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info)
+        ));
+        text
+    }
+
+    /// Render the report as a JSON document.
+    ///
+    /// ```text
+    /// {"summary":{"error":1,"warning":0,"info":0},"findings":[{"rule_id":"...","severity":"error",
+    /// "entities":["placement:C1"],"source":"board","location":{"x":0,"y":0},"message":"..."}]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let findings: Vec<String> = self
+            .findings
+            .iter()
+            .map(|f| {
+                let entities: Vec<String> = f.entities.iter().map(|e| json_string(e)).collect();
+                format!(
+                    "{{\"rule_id\":{},\"severity\":{},\"entities\":[{}],\"source\":{},\"location\":{},\"message\":{}}}",
+                    json_string(&f.rule_id),
+                    json_string(f.severity.as_str()),
+                    entities.join(","),
+                    f.source.as_deref().map_or("null".to_string(), json_string),
+                    f.location.map_or("null".to_string(), |(x, y)| format!(
+                        "{{\"x\":{},\"y\":{}}}",
+                        json_number(x),
+                        json_number(y)
+                    )),
+                    json_string(&f.message)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"summary\":{{\"error\":{},\"warning\":{},\"info\":{}}},\"findings\":[{}]}}",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info),
+            findings.join(",")
+        )
+    }
+}
+
+pub(crate) fn json_string(input: &str) -> String {
+    let mut output = String::with_capacity(input.len() + 2);
+    output.push('"');
+    for c in input.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+pub(crate) fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Whether a rule runs, and at which severity.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum RuleLevel {
+    Disabled,
+    Enabled(Severity),
+}
+
+/// Configuration of a validation run.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ValidationConfig {
+    /// Per rule overrides keyed by rule id. Rules without an entry run at their default severity.
+    pub rules: HashMap<String, RuleLevel>,
+    /// Thresholds used by the drilled hole rules.
+    pub holes: HoleCheckConfig,
+}
+
+impl ValidationConfig {
+    pub fn disable(&mut self, rule_id: &str) {
+        self.rules.insert(rule_id.to_string(), RuleLevel::Disabled);
+    }
+
+    pub fn set_severity(&mut self, rule_id: &str, severity: Severity) {
+        self.rules
+            .insert(rule_id.to_string(), RuleLevel::Enabled(severity));
+    }
+
+    /// Parse a configuration from text, one setting per line.
+    ///
+    /// Rule lines take the form `rule_id = off|info|warning|error`. The hole thresholds, in
+    /// millimetres, are set with `min_hole_to_hole`, `min_hole_to_edge` and
+    /// `hole_position_tolerance`. Blank lines and lines starting with `#` are ignored. Any other
+    /// key must be the id of one of the [default_rules]; use [ValidationConfig::parse_for_rules]
+    /// to configure custom rules as well.
+    ///
+    /// # Example
+    /// ```
+    /// use idf_parser::validation::{RuleLevel, Severity, ValidationConfig};
+    ///
+    /// let config = ValidationConfig::parse("
+    /// hole.duplicate = error
+    /// hole.hole_to_edge_clearance = off
+    /// min_hole_to_hole = 0.3
+    /// ").unwrap();
+    /// assert_eq!(config.rules["hole.duplicate"], RuleLevel::Enabled(Severity::Error));
+    /// assert_eq!(config.holes.min_hole_to_hole, 0.3);
+    /// ```
+    pub fn parse(input: &str) -> Result<ValidationConfig, String> {
+        ValidationConfig::parse_for_rules(input, &default_rules())
+    }
+
+    /// Parse a configuration like [ValidationConfig::parse], accepting the ids of the given rules,
+    /// e.g. the rules of a [Validator] with custom rules added.
+    ///
+    /// # Example
+    /// ```
+    /// use idf_parser::validation::{RuleLevel, ValidationConfig, Validator};
+    ///
+    /// let mut validator = Validator::new(ValidationConfig::default());
+    /// validator.config =
+    ///     ValidationConfig::parse_for_rules("hole.duplicate = off", &validator.rules).unwrap();
+    /// assert_eq!(validator.config.rules["hole.duplicate"], RuleLevel::Disabled);
+    /// ```
+    pub fn parse_for_rules(
+        input: &str,
+        rules: &[Box<dyn Rule>],
+    ) -> Result<ValidationConfig, String> {
+        let mut config = ValidationConfig::default();
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Line {}: expected `key = value`.", number + 1));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let threshold = || {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| format!("Line {}: invalid number {}.", number + 1, value))
+            };
+            match key {
+                "min_hole_to_hole" => config.holes.min_hole_to_hole = threshold()?,
+                "min_hole_to_edge" => config.holes.min_hole_to_edge = threshold()?,
+                "hole_position_tolerance" => config.holes.position_tolerance = threshold()?,
+                _ if !rules.iter().any(|r| r.id() == key) => {
+                    return Err(format!("Line {}: unknown rule {}.", number + 1, key));
+                }
+                _ if value == "off" => config.disable(key),
+                _ => match Severity::parse(value) {
+                    Some(severity) => config.set_severity(key, severity),
+                    None => {
+                        return Err(format!(
+                            "Line {}: unknown level {} for rule {}.",
+                            number + 1,
+                            value,
+                            key
+                        ));
+                    }
+                },
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// The files being validated.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationInput<'a> {
+    pub panel: Option<&'a BoardPanel>,
+    pub boards: &'a [BoardPanel],
+    pub libraries: &'a [Library],
+}
+
+/// A check which can be run as part of a validation.
+pub trait Rule {
+    /// A short stable identifier, used to refer to the rule in configuration and reports.
+    fn id(&self) -> &str;
+
+    /// The severity of the rule's findings unless overridden by configuration.
+    fn default_severity(&self) -> Severity;
 
-/// Validate that a Library struct contains all the components referenced in board struct.
+    /// Run the rule and return its findings. The severity of the returned findings is replaced by
+    /// the configured severity.
+    fn check(&self, input: &ValidationInput, config: &ValidationConfig) -> Vec<Finding>;
+}
+
+/// Runs a set of rules and gathers their findings.
+pub struct Validator {
+    pub rules: Vec<Box<dyn Rule>>,
+    pub config: ValidationConfig,
+}
+
+impl Validator {
+    /// Create a validator running all built in rules.
+    pub fn new(config: ValidationConfig) -> Validator {
+        Validator {
+            rules: default_rules(),
+            config,
+        }
+    }
+
+    /// Add a custom rule to the validator.
+    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push(rule);
+    }
+
+    /// Run every enabled rule and return all findings.
+    pub fn validate(&self, input: &ValidationInput) -> ValidationReport {
+        let mut findings = Vec::new();
+        for rule in self.rules.iter() {
+            let severity = match self.config.rules.get(rule.id()) {
+                Some(RuleLevel::Disabled) => continue,
+                Some(RuleLevel::Enabled(severity)) => *severity,
+                None => rule.default_severity(),
+            };
+            for mut finding in rule.check(input, &self.config) {
+                finding.severity = severity;
+                findings.push(finding);
+            }
+        }
+        ValidationReport { findings }
+    }
+}
+
+/// Every built in rule.
+pub fn default_rules() -> Vec<Box<dyn Rule>> {
+    let mut rules: Vec<Box<dyn Rule>> = vec![
+        Box::new(LibraryReferences),
+        Box::new(PanelReferences),
+        Box::new(DuplicateReferenceDesignators),
+        Box::new(PartNumberMismatch),
+        Box::new(ConflictingLibraryDefinitions),
+        Box::new(UnusedLibraryComponents),
+        Box::new(NoReferenceDesignator),
+    ];
+    for rule in HoleRule::ALL {
+        rules.push(Box::new(rule));
+    }
+    rules
+}
+
+fn finding(rule: &dyn Rule, source: &str, entities: Vec<String>, message: String) -> Finding {
+    Finding {
+        rule_id: rule.id().to_string(),
+        severity: rule.default_severity(),
+        entities,
+        source: Some(source.to_string()),
+        location: None,
+        message,
+    }
+}
+
+/// Check that the libraries contain all the components referenced in each board.
 ///
 /// A board file can reference a number of components which have properties and outlines defined in
 /// the library file. To confirm that the board file is valid, we need to check that every
@@ -25,43 +406,58 @@ use std::collections::HashSet;
 /// PROP CAPACITANCE 0.1
 /// .END_ELECTRICAL
 /// ```
-pub(crate) fn library_references_valid(
-    library: &Library,
-    board: &BoardPanel,
-) -> Result<(), String> {
-    let mut board_components = HashSet::new();
+pub struct LibraryReferences;
 
-    // Collect all component references from the board
-    for component in board.component_placements.iter() {
-        if component.reference_designator == "BOARD" {
-            continue;
-        }
-        board_components.insert(component.package_name.clone());
+impl Rule for LibraryReferences {
+    fn id(&self) -> &str {
+        "library.missing_component"
     }
 
-    let mut library_components = HashSet::new();
-    // Collect all component references from the library
-    for component in library.electrical_components.iter() {
-        library_components.insert(component.geometry_name.clone());
-    }
-    for component in library.mechanical_components.iter() {
-        library_components.insert(component.geometry_name.clone());
+    fn default_severity(&self) -> Severity {
+        Severity::Error
     }
 
-    // Check if all board components are present in the library
-    for component in board_components.iter() {
-        if !library_components.contains(component) {
-            return Err(format!(
-                "Component {} referenced in board not found in library.",
-                component
-            ));
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        // Without any library there is nothing to check the references against.
+        if input.libraries.is_empty() {
+            return Vec::new();
         }
-    }
+        let library_components: HashSet<&str> = input
+            .libraries
+            .iter()
+            .flat_map(|library| library.components())
+            .map(|component| component.geometry_name())
+            .collect();
 
-    Ok(())
+        let mut findings = Vec::new();
+        for board in input.boards.iter() {
+            for placement in board.component_placements.iter() {
+                if placement.reference_designator == "BOARD"
+                    || library_components.contains(placement.package_name.as_str())
+                {
+                    continue;
+                }
+                let mut finding = finding(
+                    self,
+                    &board.header.board_name,
+                    vec![
+                        format!("placement:{}", placement.reference_designator),
+                        format!("component:{}", placement.package_name),
+                    ],
+                    format!(
+                        "Component {} referenced in board not found in library.",
+                        placement.package_name
+                    ),
+                );
+                finding.location = Some((placement.x, placement.y));
+                findings.push(finding);
+            }
+        }
+        findings
+    }
 }
 
-/// Validate that all the boards referenced in a panel file are present.
+/// Check that all the boards referenced in a panel file are present.
 ///
 /// Any entry in .PLACEMENT section of the panel file with a reference designator of "BOARD" is
 /// considered a reference to another board. The package name of that entry is the name of the
@@ -83,36 +479,340 @@ pub(crate) fn library_references_valid(
 /// board1 THOU
 /// .END_HEADER
 /// ```
-pub(crate) fn panel_references_valid(
-    panel: &BoardPanel,
-    boards: &[BoardPanel],
-) -> Result<(), String> {
-    let mut referenced_boards = HashSet::new();
+pub struct PanelReferences;
 
-    // Collect all board references from the panel
-    for placement in panel.component_placements.iter() {
-        if placement.reference_designator == "BOARD" {
-            referenced_boards.insert(placement.package_name.clone());
+impl Rule for PanelReferences {
+    fn id(&self) -> &str {
+        "panel.missing_board"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        let Some(panel) = input.panel else {
+            return Vec::new();
+        };
+        let mut reported = HashSet::new();
+        let mut findings = Vec::new();
+        for placement in panel.component_placements.iter() {
+            if placement.reference_designator != "BOARD"
+                || input
+                    .boards
+                    .iter()
+                    .any(|board| board.header.board_name == placement.package_name)
+                || !reported.insert(placement.package_name.as_str())
+            {
+                continue;
+            }
+            let mut finding = finding(
+                self,
+                &panel.header.board_name,
+                vec![format!("board:{}", placement.package_name)],
+                format!(
+                    "Board {} referenced in panel not found.",
+                    placement.package_name
+                ),
+            );
+            finding.location = Some((placement.x, placement.y));
+            findings.push(finding);
         }
+        findings
     }
+}
 
-    // Check if all referenced boards are present
-    for ref_board in referenced_boards.iter() {
-        if !boards
-            .iter()
-            .any(|board| board.header.board_name == *ref_board)
-        {
-            return Err(format!(
-                "Board {} referenced in panel not found.",
-                ref_board
+/// Every component of every library, together with the source naming the library it belongs to.
+fn library_components(libraries: &[Library]) -> Vec<(String, Component<'_>)> {
+    libraries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, library)| {
+            library
+                .components()
+                .map(move |c| (format!("library[{}]", i), c))
+        })
+        .collect()
+}
+
+/// Check that every reference designator is used by only one placement on a board.
+///
+/// `NOREFDES` and `BOARD` placements are excluded as they are not unique by definition.
+pub struct DuplicateReferenceDesignators;
+
+impl Rule for DuplicateReferenceDesignators {
+    fn id(&self) -> &str {
+        "placement.duplicate_refdes"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for board in input.boards.iter() {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for placement in board.component_placements.iter() {
+                *counts
+                    .entry(placement.reference_designator.as_str())
+                    .or_default() += 1;
+            }
+            let mut reported = HashSet::new();
+            for placement in board.component_placements.iter() {
+                let refdes = placement.reference_designator.as_str();
+                if refdes == "NOREFDES" || refdes == "BOARD" || counts[refdes] < 2 {
+                    continue;
+                }
+                if !reported.insert(refdes) {
+                    continue;
+                }
+                let mut finding = finding(
+                    self,
+                    &board.header.board_name,
+                    vec![format!("placement:{}", refdes)],
+                    format!(
+                        "Reference designator {} is used by {} placements.",
+                        refdes, counts[refdes]
+                    ),
+                );
+                finding.location = Some((placement.x, placement.y));
+                findings.push(finding);
+            }
+        }
+        findings
+    }
+}
+
+/// Check that the part number of each placement matches a library component with the same
+/// geometry name.
+///
+/// For example the placement below refers to geometry "cc1210" with part number "pn-cc1210", so a
+/// library entry `cc1210 pn-cc1210 ...` is expected. Placements whose geometry is missing from the
+/// library entirely are reported by `library.missing_component` instead.
+/// ```text
+/// .PLACEMENT
+/// cc1210 pn-cc1210 C2
+/// 3000.0 3500.0 0.0 0.0 TOP PLACED
+/// .END_PLACEMENT
+/// ```
+pub struct PartNumberMismatch;
+
+impl Rule for PartNumberMismatch {
+    fn id(&self) -> &str {
+        "placement.part_number_mismatch"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        let mut part_numbers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (_, component) in library_components(input.libraries) {
+            part_numbers
+                .entry(component.geometry_name())
+                .or_default()
+                .push(component.part_number());
+        }
+
+        let mut findings = Vec::new();
+        for board in input.boards.iter() {
+            for placement in board.component_placements.iter() {
+                if placement.reference_designator == "BOARD" {
+                    continue;
+                }
+                let Some(known) = part_numbers.get(placement.package_name.as_str()) else {
+                    continue;
+                };
+                if known.contains(&placement.part_number.as_str()) {
+                    continue;
+                }
+                let mut finding = finding(
+                    self,
+                    &board.header.board_name,
+                    vec![
+                        format!("placement:{}", placement.reference_designator),
+                        format!("component:{}", placement.package_name),
+                    ],
+                    format!(
+                        "{} has part number {} but library component {} has part number {}.",
+                        placement.reference_designator,
+                        placement.part_number,
+                        placement.package_name,
+                        known.join(", ")
+                    ),
+                );
+                finding.location = Some((placement.x, placement.y));
+                findings.push(finding);
+            }
+        }
+        findings
+    }
+}
+
+/// Check that a library component, identified by geometry name and part number, is not defined more
+/// than once with a different height, units or outline.
+pub struct ConflictingLibraryDefinitions;
+
+impl Rule for ConflictingLibraryDefinitions {
+    fn id(&self) -> &str {
+        "library.conflicting_definition"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        let mut first: HashMap<(&str, &str), Component> = HashMap::new();
+        let mut findings = Vec::new();
+        for (source, component) in library_components(input.libraries) {
+            let key = (component.geometry_name(), component.part_number());
+            let Some(existing) = first.get(&key) else {
+                first.insert(key, component);
+                continue;
+            };
+            if existing.units() == component.units()
+                && existing.height() == component.height()
+                && existing.outline() == component.outline()
+            {
+                continue;
+            }
+            findings.push(finding(
+                self,
+                &source,
+                vec![format!("component:{}", component.geometry_name())],
+                format!(
+                    "Component {} ({}) is defined more than once with different geometry.",
+                    component.geometry_name(),
+                    component.part_number()
+                ),
             ));
         }
+        findings
     }
+}
 
-    Ok(())
+/// Report library components which are not placed on any of the boards.
+pub struct UnusedLibraryComponents;
+
+impl Rule for UnusedLibraryComponents {
+    fn id(&self) -> &str {
+        "library.unused_component"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        if input.boards.is_empty() {
+            return Vec::new();
+        }
+        let used: HashSet<&str> = input
+            .boards
+            .iter()
+            .flat_map(|b| b.component_placements.iter())
+            .map(|p| p.package_name.as_str())
+            .collect();
+        let mut reported = HashSet::new();
+        library_components(input.libraries)
+            .into_iter()
+            .filter(|(_, c)| {
+                !used.contains(c.geometry_name()) && reported.insert(c.geometry_name())
+            })
+            .map(|(source, c)| {
+                finding(
+                    self,
+                    &source,
+                    vec![format!("component:{}", c.geometry_name())],
+                    format!(
+                        "Component {} is not placed on any board.",
+                        c.geometry_name()
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Report placements without a reference designator.
+///
+/// `NOREFDES` is permitted by the specification for parts such as mechanical fixings, but such
+/// placements cannot be cross referenced with holes or other data from the ECAD system.
+pub struct NoReferenceDesignator;
+
+impl Rule for NoReferenceDesignator {
+    fn id(&self) -> &str {
+        "placement.norefdes"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&self, input: &ValidationInput, _config: &ValidationConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for board in input.boards.iter() {
+            for placement in board.component_placements.iter() {
+                if placement.reference_designator != "NOREFDES" {
+                    continue;
+                }
+                let mut finding = finding(
+                    self,
+                    &board.header.board_name,
+                    vec![format!("component:{}", placement.package_name)],
+                    format!(
+                        "{} ({}) is placed without a reference designator.",
+                        placement.package_name, placement.part_number
+                    ),
+                );
+                finding.location = Some((placement.x, placement.y));
+                findings.push(finding);
+            }
+        }
+        findings
+    }
+}
+
+impl Rule for HoleRule {
+    fn id(&self) -> &str {
+        HoleRule::id(self)
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self {
+            HoleRule::OutsideOutline
+            | HoleRule::InsideCutout
+            | HoleRule::Coincident
+            | HoleRule::ViaInKeepout => Severity::Error,
+            HoleRule::Duplicate
+            | HoleRule::HoleToHoleClearance
+            | HoleRule::HoleToEdgeClearance
+            | HoleRule::UnknownPinPart => Severity::Warning,
+        }
+    }
+
+    fn check(&self, input: &ValidationInput, config: &ValidationConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for board in input.panel.iter().copied().chain(input.boards.iter()) {
+            for violation in check_hole_rule(board, *self, &config.holes) {
+                let entities = violation
+                    .holes
+                    .iter()
+                    .map(|i| format!("hole:{}", i))
+                    .collect();
+                let mut finding =
+                    finding(self, &board.header.board_name, entities, violation.message);
+                finding.location = Some((violation.x, violation.y));
+                findings.push(finding);
+            }
+        }
+        findings
+    }
 }
 
-// test
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,59 +820,255 @@ mod tests {
     use crate::parse_board_file;
     use crate::parse_library_file;
 
-    #[test]
-    fn test_library_references_valid() {
-        let library = parse_library_file("src/test_files/library.emp").unwrap();
-        let board = parse_board_file("src/test_files/board.emn").unwrap();
-
-        let result = library_references_valid(&library, &board);
-
-        result.unwrap();
-
-        // Add dummy component to board struct
-        let dummy_component = ComponentPlacement {
-            package_name: "dummy_component".to_string(),
+    fn dummy_placement(package_name: &str, reference_designator: &str) -> ComponentPlacement {
+        ComponentPlacement {
+            package_name: package_name.to_string(),
             part_number: "dummy_part".to_string(),
-            reference_designator: "DUMMY".to_string(),
+            reference_designator: reference_designator.to_string(),
             x: 0.0,
             y: 0.0,
             mounting_offset: 0.0,
             rotation_angle: 0.0,
             board_side: "TOP".to_string(),
             placement_status: "PLACED".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_library_references() {
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+
+        let mut boards = vec![board];
+        let libraries = [library];
+        let validator = Validator::new(ValidationConfig::default());
+        let input = ValidationInput {
+            panel: None,
+            boards: &boards,
+            libraries: &libraries,
         };
-        let mut board = board.clone();
-        board.component_placements.push(dummy_component);
+        let report = validator.validate(&input);
+        assert_eq!(report.by_rule("library.missing_component").count(), 0);
 
-        // Test with dummy component
-        let result = library_references_valid(&library, &board);
-        assert!(result.is_err());
+        // Add dummy component to board struct
+        boards[0]
+            .component_placements
+            .push(dummy_placement("dummy_component", "DUMMY"));
+        let input = ValidationInput {
+            panel: None,
+            boards: &boards,
+            libraries: &libraries,
+        };
+        let report = validator.validate(&input);
+        let findings: Vec<_> = report.by_rule("library.missing_component").collect();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(
+            findings[0]
+                .entities
+                .contains(&"placement:DUMMY".to_string())
+        );
     }
+
     #[test]
-    fn test_panel_references_valid() {
-        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+    fn test_panel_references() {
+        let mut panel = parse_board_file("src/test_files/panel.emn").unwrap();
         let boards = vec![parse_board_file("src/test_files/board.emn").unwrap()];
+        let validator = Validator::new(ValidationConfig::default());
 
-        let result = panel_references_valid(&panel, &boards);
+        let input = ValidationInput {
+            panel: Some(&panel),
+            boards: &boards,
+            libraries: &[],
+        };
+        assert_eq!(
+            validator
+                .validate(&input)
+                .by_rule("panel.missing_board")
+                .count(),
+            0
+        );
 
-        result.unwrap();
+        // Add dummy board placement to panel struct
+        panel
+            .component_placements
+            .push(dummy_placement("dummy_board", "BOARD"));
+        let input = ValidationInput {
+            panel: Some(&panel),
+            boards: &boards,
+            libraries: &[],
+        };
+        assert!(validator.validate(&input).has_errors());
+    }
 
-        // Add dummy component placement to panel struct
-        let dummy_component = ComponentPlacement {
-            package_name: "dummy_board".to_string(),
-            part_number: "dummy_part".to_string(),
-            reference_designator: "BOARD".to_string(),
-            x: 0.0,
-            y: 0.0,
-            mounting_offset: 0.0,
-            rotation_angle: 0.0,
-            board_side: "TOP".to_string(),
-            placement_status: "PLACED".to_string(),
+    #[test]
+    fn test_rule_levels() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let duplicate = board.drilled_holes[0].clone();
+        board.drilled_holes.push(duplicate);
+        let boards = [board];
+        let input = ValidationInput {
+            panel: None,
+            boards: &boards,
+            libraries: &[],
         };
-        let mut panel = panel.clone();
-        panel.component_placements.push(dummy_component);
-        // Test with dummy component
-        let result = panel_references_valid(&panel, &boards);
-        assert!(result.is_err());
+
+        let report = Validator::new(ValidationConfig::default()).validate(&input);
+        assert_eq!(report.by_rule("hole.duplicate").count(), 1);
+        assert_eq!(report.count(Severity::Warning), 1);
+        assert!(!report.has_errors());
+
+        let mut config = ValidationConfig::default();
+        config.set_severity("hole.duplicate", Severity::Error);
+        let report = Validator::new(config).validate(&input);
+        assert!(report.has_errors());
+
+        let mut config = ValidationConfig::default();
+        config.disable("hole.duplicate");
+        let report = Validator::new(config).validate(&input);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = ValidationConfig::parse(
+            "hole.duplicate = error\n# comment\n\nhole.via_in_keepout = off\nmin_hole_to_edge = 1.5",
+        )
+        .unwrap();
+        assert_eq!(
+            config.rules["hole.duplicate"],
+            RuleLevel::Enabled(Severity::Error)
+        );
+        assert_eq!(config.rules["hole.via_in_keepout"], RuleLevel::Disabled);
+        assert_eq!(config.holes.min_hole_to_edge, 1.5);
+
+        assert!(ValidationConfig::parse("hole.duplicate = loud").is_err());
+        assert!(ValidationConfig::parse("hole.duplicate").is_err());
+        assert_eq!(
+            ValidationConfig::parse("hole.duplicate = error\nhole.dupilcate = off"),
+            Err("Line 2: unknown rule hole.dupilcate.".to_string())
+        );
+        assert_eq!(
+            ValidationConfig::parse("min_hole_to_corner = 1"),
+            Err("Line 1: unknown rule min_hole_to_corner.".to_string())
+        );
+    }
+
+    struct OutlineThickness;
+
+    impl Rule for OutlineThickness {
+        fn id(&self) -> &str {
+            "board.outline_thickness"
+        }
+
+        fn default_severity(&self) -> Severity {
+            Severity::Warning
+        }
+
+        fn check(&self, _: &ValidationInput, _: &ValidationConfig) -> Vec<Finding> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_parse_config_for_custom_rules() {
+        let mut validator = Validator::new(ValidationConfig::default());
+        validator.add_rule(Box::new(OutlineThickness));
+        let config = ValidationConfig::parse_for_rules(
+            "board.outline_thickness = error\nhole.duplicate = off",
+            &validator.rules,
+        )
+        .unwrap();
+        assert_eq!(
+            config.rules["board.outline_thickness"],
+            RuleLevel::Enabled(Severity::Error)
+        );
+        assert_eq!(config.rules["hole.duplicate"], RuleLevel::Disabled);
+
+        // The built in rules alone do not know the custom rule.
+        assert_eq!(
+            ValidationConfig::parse("board.outline_thickness = error"),
+            Err("Line 1: unknown rule board.outline_thickness.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_report() {
+        let report = ValidationReport {
+            findings: vec![Finding {
+                rule_id: "library.missing_component".to_string(),
+                severity: Severity::Error,
+                entities: vec!["placement:C1".to_string()],
+                source: Some("sample_board".to_string()),
+                location: Some((1.5, 2.0)),
+                message: "Component \"x\" missing.".to_string(),
+            }],
+        };
+        assert_eq!(
+            report.to_text(),
+            "error[library.missing_component] sample_board (1.5, 2): Component \"x\" missing.\n1 error(s), 0 warning(s), 0 info\n"
+        );
+        assert_eq!(
+            report.to_json(),
+            "{\"summary\":{\"error\":1,\"warning\":0,\"info\":0},\"findings\":[{\"rule_id\":\"library.missing_component\",\"severity\":\"error\",\"entities\":[\"placement:C1\"],\"source\":\"sample_board\",\"location\":{\"x\":1.5,\"y\":2},\"message\":\"Component \\\"x\\\" missing.\"}]}"
+        );
+    }
+
+    #[test]
+    fn test_reference_designator_rules() {
+        let mut board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        board
+            .component_placements
+            .push(dummy_placement("cc1210", "C2"));
+        board
+            .component_placements
+            .push(dummy_placement("cc1210", "NOREFDES"));
+        let boards = [board];
+        let libraries = [library];
+        let input = ValidationInput {
+            panel: None,
+            boards: &boards,
+            libraries: &libraries,
+        };
+        let report = Validator::new(ValidationConfig::default()).validate(&input);
+
+        let duplicates: Vec<_> = report.by_rule("placement.duplicate_refdes").collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].entities, vec!["placement:C2".to_string()]);
+        // Both dummy placements use part number "dummy_part" rather than "pn-cc1210".
+        assert_eq!(report.by_rule("placement.part_number_mismatch").count(), 2);
+        assert_eq!(report.by_rule("placement.norefdes").count(), 1);
+        assert_eq!(report.by_rule("library.unused_component").count(), 0);
+    }
+
+    #[test]
+    fn test_library_definition_rules() {
+        let board = parse_board_file("src/test_files/board.emn").unwrap();
+        let library = parse_library_file("src/test_files/library.emp").unwrap();
+        let mut other = library.clone();
+        other.electrical_components[0].height += 10.0;
+        other.electrical_components.truncate(1);
+        let mut unused = library.electrical_components[1].clone();
+        unused.geometry_name = "unused".to_string();
+        other.electrical_components.push(unused);
+
+        let boards = [board];
+        let libraries = [library, other];
+        let input = ValidationInput {
+            panel: None,
+            boards: &boards,
+            libraries: &libraries,
+        };
+        let report = Validator::new(ValidationConfig::default()).validate(&input);
+
+        let conflicts: Vec<_> = report.by_rule("library.conflicting_definition").collect();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].entities, vec!["component:cs13_a".to_string()]);
+        assert_eq!(conflicts[0].source.as_deref(), Some("library[1]"));
+        let unused: Vec<_> = report.by_rule("library.unused_component").collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].source.as_deref(), Some("library[1]"));
+        assert_eq!(unused[0].severity, Severity::Info);
     }
 }
@@ -0,0 +1,319 @@
+use crate::board::BoardPanel;
+use crate::component_placement::ComponentPlacement;
+use crate::drilled_holes::Hole;
+use crate::library::Library;
+use crate::notes::Note;
+use crate::outlines::{
+    BoardPanelOutline, OtherOutline, PlacementGroupArea, PlacementKeepout, PlacementOutline,
+    RoutingKeepout, RoutingOutline, ViaKeepout,
+};
+use crate::point::Point;
+
+/// Quote a field such as a part number if it contains whitespace.
+fn field(value: &str) -> String {
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_points(output: &mut String, points: &[Point]) {
+    for point in points.iter() {
+        output.push_str(&format!(
+            "{} {} {} {}\n",
+            point.loop_label, point.x, point.y, point.angle
+        ));
+    }
+}
+
+/// Write a board or panel outline section, `section` being BOARD_OUTLINE or PANEL_OUTLINE.
+pub(crate) fn write_outline(output: &mut String, section: &str, outline: &BoardPanelOutline) {
+    output.push_str(&format!(".{} {}\n", section, outline.owner));
+    output.push_str(&format!("{}\n", outline.thickness));
+    write_points(output, &outline.outline);
+    output.push_str(&format!(".END_{}\n", section));
+}
+
+pub(crate) fn write_other_outline(output: &mut String, outline: &OtherOutline) {
+    output.push_str(&format!(".OTHER_OUTLINE {}\n", outline.owner));
+    output.push_str(&format!(
+        "{} {} {}\n",
+        outline.id, outline.extrude_thickness, outline.board_side
+    ));
+    write_points(output, &outline.outline);
+    output.push_str(".END_OTHER_OUTLINE\n");
+}
+
+pub(crate) fn write_routing_outline(output: &mut String, outline: &RoutingOutline) {
+    output.push_str(&format!(".ROUTE_OUTLINE {}\n", outline.owner));
+    output.push_str(&format!("{}\n", outline.routing_layers));
+    write_points(output, &outline.outline);
+    output.push_str(".END_ROUTE_OUTLINE\n");
+}
+
+pub(crate) fn write_placement_outline(output: &mut String, outline: &PlacementOutline) {
+    output.push_str(&format!(".PLACE_OUTLINE {}\n", outline.owner));
+    output.push_str(&format!(
+        "{} {}\n",
+        outline.board_side, outline.outline_height
+    ));
+    write_points(output, &outline.outline);
+    output.push_str(".END_PLACE_OUTLINE\n");
+}
+
+pub(crate) fn write_routing_keepout(output: &mut String, keepout: &RoutingKeepout) {
+    output.push_str(&format!(".ROUTE_KEEPOUT {}\n", keepout.owner));
+    output.push_str(&format!("{}\n", keepout.routing_layers));
+    write_points(output, &keepout.outline);
+    output.push_str(".END_ROUTE_KEEPOUT\n");
+}
+
+pub(crate) fn write_via_keepout(output: &mut String, keepout: &ViaKeepout) {
+    output.push_str(&format!(".VIA_KEEPOUT {}\n", keepout.owner));
+    write_points(output, &keepout.outline);
+    output.push_str(".END_VIA_KEEPOUT\n");
+}
+
+pub(crate) fn write_placement_keepout(output: &mut String, keepout: &PlacementKeepout) {
+    output.push_str(&format!(".PLACE_KEEPOUT {}\n", keepout.owner));
+    output.push_str(&format!(
+        "{} {}\n",
+        keepout.board_side, keepout.keepout_height
+    ));
+    write_points(output, &keepout.outline);
+    output.push_str(".END_PLACE_KEEPOUT\n");
+}
+
+pub(crate) fn write_placement_group_area(output: &mut String, area: &PlacementGroupArea) {
+    output.push_str(&format!(".PLACE_REGION {}\n", area.owner));
+    output.push_str(&format!("{} {}\n", area.board_side, area.group_name));
+    write_points(output, &area.outline);
+    output.push_str(".END_PLACE_REGION\n");
+}
+
+/// Write one line of the drilled holes section.
+pub(crate) fn write_hole(output: &mut String, hole: &Hole) {
+    output.push_str(&format!(
+        "{} {} {} {} {} {} {}\n",
+        hole.diameter,
+        hole.x,
+        hole.y,
+        hole.plating_style,
+        hole.associated_part,
+        hole.hole_type,
+        hole.owner
+    ));
+}
+
+/// Write one line of the notes section.
+pub(crate) fn write_note(output: &mut String, note: &Note) {
+    output.push_str(&format!(
+        "{} {} {} {} \"{}\"\n",
+        note.x, note.y, note.text_height, note.test_string_physical_length, note.text
+    ));
+}
+
+/// Write the two lines of a component placement.
+pub(crate) fn write_placement(output: &mut String, placement: &ComponentPlacement) {
+    output.push_str(&format!(
+        "{} {} {}\n",
+        placement.package_name,
+        field(&placement.part_number),
+        placement.reference_designator
+    ));
+    output.push_str(&format!(
+        "{} {} {} {} {} {}\n",
+        placement.x,
+        placement.y,
+        placement.mounting_offset,
+        placement.rotation_angle,
+        placement.board_side,
+        placement.placement_status
+    ));
+}
+
+/// Write a board or panel to the text of a .emn file.
+/// File specification: http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=8
+///
+/// # Example
+/// ```
+/// use idf_parser::board::parse_board_or_panel;
+/// use idf_parser::parse_board_file;
+/// use idf_parser::writer::write_board_or_panel;
+///
+/// let board = parse_board_file("src/test_files/board.emn").unwrap();
+/// let text = write_board_or_panel(&board);
+/// assert_eq!(parse_board_or_panel(&text).unwrap(), board);
+/// ```
+pub fn write_board_or_panel(board: &BoardPanel) -> String {
+    let mut output = String::new();
+    let header = &board.header;
+
+    output.push_str(".HEADER\n");
+    output.push_str(&format!(
+        "{} 3.0 \"{}\" {} {}\n",
+        header.file_type, header.system_id, header.date, header.file_version
+    ));
+    output.push_str(&format!("{} {}\n", header.board_name, header.units));
+    output.push_str(".END_HEADER\n");
+
+    let outline_section = if header.file_type == "PANEL_FILE" {
+        "PANEL_OUTLINE"
+    } else {
+        "BOARD_OUTLINE"
+    };
+    write_outline(&mut output, outline_section, &board.outline);
+    for outline in board.other_outlines.iter() {
+        write_other_outline(&mut output, outline);
+    }
+    for outline in board.routing_outlines.iter() {
+        write_routing_outline(&mut output, outline);
+    }
+    for outline in board.placement_outlines.iter() {
+        write_placement_outline(&mut output, outline);
+    }
+    for keepout in board.routing_keepouts.iter() {
+        write_routing_keepout(&mut output, keepout);
+    }
+    for keepout in board.via_keepouts.iter() {
+        write_via_keepout(&mut output, keepout);
+    }
+    for keepout in board.placement_keepouts.iter() {
+        write_placement_keepout(&mut output, keepout);
+    }
+    for area in board.placement_group_areas.iter() {
+        write_placement_group_area(&mut output, area);
+    }
+
+    output.push_str(".DRILLED_HOLES\n");
+    for hole in board.drilled_holes.iter() {
+        write_hole(&mut output, hole);
+    }
+    output.push_str(".END_DRILLED_HOLES\n");
+
+    // The notes section is optional but may not be empty.
+    if !board.notes.is_empty() {
+        output.push_str(".NOTES\n");
+        for note in board.notes.iter() {
+            write_note(&mut output, note);
+        }
+        output.push_str(".END_NOTES\n");
+    }
+
+    output.push_str(".PLACEMENT\n");
+    for placement in board.component_placements.iter() {
+        write_placement(&mut output, placement);
+    }
+    output.push_str(".END_PLACEMENT\n");
+
+    output
+}
+
+/// Write a library to the text of a .emp file.
+/// File specification: http://www.aertia.com/docs/priware/IDF_V30_Spec.pdf#page=29
+///
+/// Electrical components are written before mechanical components, and the properties of each
+/// electrical component are written in alphabetical order.
+///
+/// # Example
+/// ```
+/// use idf_parser::library::parse_library;
+/// use idf_parser::parse_library_file;
+/// use idf_parser::writer::write_library;
+///
+/// let library = parse_library_file("src/test_files/library.emp").unwrap();
+/// let text = write_library(&library);
+/// assert_eq!(parse_library(&text).unwrap(), library);
+/// ```
+pub fn write_library(library: &Library) -> String {
+    let mut output = String::new();
+    let header = &library.header;
+
+    output.push_str(".HEADER\n");
+    output.push_str(&format!(
+        "LIBRARY_FILE 3.0 \"{}\" {} {}\n",
+        header.system_id, header.date, header.file_version
+    ));
+    output.push_str(".END_HEADER\n");
+
+    for component in library.electrical_components.iter() {
+        output.push_str(".ELECTRICAL\n");
+        output.push_str(&format!(
+            "{} {} {} {}\n",
+            component.geometry_name,
+            field(&component.part_number),
+            component.units,
+            component.height
+        ));
+        write_points(&mut output, &component.outline);
+        let mut properties: Vec<_> = component.properties.iter().collect();
+        properties.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in properties {
+            output.push_str(&format!("PROP {} {}\n", name, value));
+        }
+        output.push_str(".END_ELECTRICAL\n");
+    }
+
+    for component in library.mechanical_components.iter() {
+        output.push_str(".MECHANICAL\n");
+        output.push_str(&format!(
+            "{} {} {} {}\n",
+            component.geometry_name,
+            field(&component.part_number),
+            component.units,
+            component.height
+        ));
+        write_points(&mut output, &component.outline);
+        output.push_str(".END_MECHANICAL\n");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board_or_panel;
+    use crate::library::parse_library;
+    use crate::{parse_board_file, parse_library_file};
+
+    #[test]
+    fn test_write_board_round_trip() {
+        for file in [
+            "src/test_files/board.emn",
+            "src/test_files/panel.emn",
+            "src/test_files/ISOL.emn",
+            "src/test_files/beaglebone.emn",
+            "src/test_files/ain.emn",
+            "src/test_files/esp.emn",
+        ] {
+            let board = parse_board_file(file).unwrap();
+            let text = write_board_or_panel(&board);
+            assert_eq!(parse_board_or_panel(&text).unwrap(), board, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_write_library_round_trip() {
+        for file in [
+            "src/test_files/library.emp",
+            "src/test_files/ISOL.emp",
+            "src/test_files/beaglebone.emp",
+            "src/test_files/ain.emp",
+            "src/test_files/esp.emp",
+        ] {
+            let library = parse_library_file(file).unwrap();
+            let text = write_library(&library);
+            assert_eq!(parse_library(&text).unwrap(), library, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_write_panel_outline_section() {
+        let panel = parse_board_file("src/test_files/panel.emn").unwrap();
+        let text = write_board_or_panel(&panel);
+        assert!(text.contains(".PANEL_OUTLINE MCAD\n62\n0 0 0 0\n"));
+        assert!(!text.contains(".NOTES"));
+    }
+}